@@ -0,0 +1,37 @@
+class CookMode {
+  _current = 0;
+
+  constructor() {
+    this.steps = document.querySelectorAll("section.step");
+    document.querySelector("#previous-step")?.addEventListener("click", () => this.show(this._current - 1));
+    document.querySelector("#next-step")?.addEventListener("click", () => this.show(this._current + 1));
+    document.addEventListener("keydown", (event) => {
+      // Keys pressed on controls (e.g. Space on a button, or arrows in a timer's input) are theirs
+      const target = event.target;
+      if (target.isContentEditable || target.closest?.("a, button, input, select, textarea, summary")) {
+        return;
+      }
+
+      if (event.key == "ArrowLeft") {
+        this.show(this._current - 1);
+      } else if (event.key == "ArrowRight" || event.key == " ") {
+        event.preventDefault();
+        this.show(this._current + 1);
+      }
+    });
+
+    this.show(0);
+  }
+
+  show(index) {
+    if (index < 0 || index >= this.steps.length) {
+      return;
+    }
+
+    this.steps.forEach((step, i) => { step.hidden = (i != index); });
+    this._current = index;
+  }
+}
+
+document.addEventListener("DOMContentLoaded", () => new CookMode());
+// vim set: ts=4 sts=4 sw=4 et:
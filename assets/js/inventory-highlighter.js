@@ -0,0 +1,35 @@
+function inStock() {
+  const recipe = document.querySelector("article.recipe");
+  return JSON.parse(recipe?.dataset.inStock ?? "[]");
+}
+
+// Matches an item as whole words (allowing simple plurals), so "egg" matches "2 eggs" but not
+// "eggplant", and "salt" doesn't match "unsalted"
+function itemPattern(item) {
+  const escaped = item.trim().replace(/[.*+?^${}()|[\]\\]/g, "\\$&").replace(/\s+/g, "\\s+");
+  return new RegExp(`(?<![\\p{L}\\p{N}])${escaped}(?:s|es)?(?![\\p{L}\\p{N}])`, "iu");
+}
+
+async function highlightIngredients() {
+  const patterns = inStock()
+    .filter((item) => item.trim() != "")
+    .map(itemPattern);
+  if (patterns.length == 0) {
+    return;
+  }
+
+  const ingredients = document.querySelectorAll("h2#ingredients + ul li, h3.ingredients + ul li");
+  ingredients.forEach((el) => {
+    if (patterns.some((pattern) => pattern.test(el.textContent))) {
+      el.classList.add("in-stock");
+    }
+  });
+}
+
+document.addEventListener("DOMContentLoaded", highlightIngredients);
+document.addEventListener("htmx:afterSettle", (ev) => {
+  if (ev.target.matches?.("article.recipe")) {
+    highlightIngredients();
+  }
+});
+// vim set: ts=4 sts=4 sw=4 et:
@@ -0,0 +1,19 @@
+async function flagDanglingLinks() {
+  const recipe = document.querySelector("article.recipe");
+  const dangling = JSON.parse(recipe?.dataset.dangling ?? "[]");
+
+  dangling.forEach((slug) => {
+    document.querySelectorAll(`article.recipe a[href="/recipe/${slug}"]`).forEach((el) => {
+      el.classList.add("dangling");
+      el.setAttribute("title", "This recipe does not exist yet");
+    });
+  });
+}
+
+document.addEventListener("DOMContentLoaded", flagDanglingLinks);
+document.addEventListener("htmx:afterSettle", (ev) => {
+  if (ev.target.matches?.("article.recipe")) {
+    flagDanglingLinks();
+  }
+});
+// vim set: ts=4 sts=4 sw=4 et:
@@ -12,7 +12,15 @@ async function attachClickHandlers() {
 
   const nested_lists = document.querySelectorAll("h3 + ul li");
   nested_lists.forEach(attachHandler);
+
+  const embedded_lists = document.querySelectorAll("details.transclusion h4 + ul li, details.transclusion h5 + ul li");
+  embedded_lists.forEach(attachHandler);
 }
 
 document.addEventListener("DOMContentLoaded", attachClickHandlers);
+document.addEventListener("htmx:afterSettle", (ev) => {
+  if (ev.target.matches?.("article.recipe")) {
+    attachClickHandlers();
+  }
+});
 // vim set: ts=4 sts=4 sw=4 et:
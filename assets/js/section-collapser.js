@@ -40,4 +40,9 @@ async function modifyLists() {
 }
 
 document.addEventListener("DOMContentLoaded", modifyLists);
+document.addEventListener("htmx:afterSettle", (ev) => {
+  if (ev.target.matches?.("article.recipe")) {
+    modifyLists();
+  }
+});
 // vim set: ts=4 sts=4 sw=4 et:
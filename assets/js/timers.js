@@ -0,0 +1,44 @@
+function toggleTimer(button) {
+  if (button.dataset.interval) {
+    clearInterval(Number(button.dataset.interval));
+    delete button.dataset.interval;
+    button.textContent = button.dataset.label;
+    button.classList.add("secondary");
+    return;
+  }
+
+  button.dataset.label ??= button.textContent;
+  let remaining = Number(button.dataset.seconds);
+  button.classList.remove("secondary");
+
+  const tick = () => {
+    const minutes = Math.floor(remaining / 60);
+    const seconds = String(remaining % 60).padStart(2, "0");
+    button.textContent = `⏲ ${minutes}:${seconds}`;
+
+    if (remaining <= 0) {
+      clearInterval(Number(button.dataset.interval));
+      delete button.dataset.interval;
+      button.textContent = `⏰ ${button.dataset.label.replace("⏲ ", "")} done!`;
+      navigator.vibrate?.([500, 250, 500]);
+    }
+    remaining -= 1;
+  };
+
+  tick();
+  button.dataset.interval = setInterval(tick, 1000);
+}
+
+async function attachTimers() {
+  document.querySelectorAll("button.timer").forEach((button) => {
+    button.addEventListener("click", () => toggleTimer(button));
+  });
+}
+
+document.addEventListener("DOMContentLoaded", attachTimers);
+document.addEventListener("htmx:afterSettle", (ev) => {
+  if (ev.target.matches?.("article.recipe")) {
+    attachTimers();
+  }
+});
+// vim set: ts=4 sts=4 sw=4 et:
@@ -0,0 +1,234 @@
+use crate::library::{self, Libraries, Library};
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::Url;
+
+/// The name of the configuration file within a recipe directory.
+pub const FILE_NAME: &str = "_pantry.toml";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to read {0:?}: {1}")]
+    Io(PathBuf, #[source] io::Error),
+    #[error("invalid configuration in {0:?}: {1}")]
+    Parse(PathBuf, #[source] toml::de::Error),
+    #[error("failed to render configuration: {0}")]
+    Render(#[from] toml::ser::Error),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum WatcherBackend {
+    /// Use the platform's native filesystem notifications
+    Native,
+    /// Periodically scan the recipe directory, for network filesystems and bind-mounts
+    Poll,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldKind {
+    /// Searched as free text, e.g. `cuisine:sichuan`
+    #[default]
+    Text,
+    /// Matched exactly, as a filter, e.g. `diet:"gluten free"`
+    Filter,
+}
+
+/// How a custom frontmatter field is searched.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Field {
+    pub kind: FieldKind,
+    /// Whether to count the field's values among the search filters
+    pub facet: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Search {
+    /// How many results to show per page
+    pub page_size: u32,
+    /// The language used to stem words when indexing and searching
+    pub language: String,
+    /// Words which should also match other words, e.g. `scallion = ["green onion"]`
+    pub synonyms: BTreeMap<String, Vec<String>>,
+    /// Custom frontmatter fields to make searchable, e.g. `[search.fields.cuisine]`
+    pub fields: BTreeMap<String, Field>,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            page_size: 50,
+            language: String::from("en"),
+            synonyms: Default::default(),
+            fields: Default::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Watcher {
+    pub backend: WatcherBackend,
+    /// How often the polling watcher scans the recipe directory, in seconds
+    pub poll_interval: u64,
+    /// How long to collect filesystem events for, before reindexing the affected recipes
+    pub debounce_ms: u64,
+    /// How often to check for changes the filesystem watcher missed, in seconds (0 to disable)
+    pub reconcile_interval: u64,
+}
+
+impl Default for Watcher {
+    fn default() -> Self {
+        Self {
+            backend: WatcherBackend::Native,
+            poll_interval: 2,
+            debounce_ms: 500,
+            reconcile_interval: 300,
+        }
+    }
+}
+
+/// Settings read from a TOML file, either given explicitly, or discovered in the user's config
+/// directory and then the recipe directory (the latter taking precedence). Command-line arguments
+/// and environment variables override both.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub listen_on: SocketAddr,
+    /// The public URL Pantry is reachable at, e.g. behind a reverse proxy, used to link to it from
+    /// outside (such as from a saved shopping list)
+    pub base_url: Option<Url>,
+    pub recipe_dir: Option<PathBuf>,
+    pub libraries: BTreeMap<String, PathBuf>,
+    pub search: Search,
+    pub watcher: Watcher,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            listen_on: SocketAddr::from(([127, 0, 0, 1], 3000)),
+            base_url: None,
+            recipe_dir: None,
+            libraries: Default::default(),
+            search: Default::default(),
+            watcher: Default::default(),
+        }
+    }
+}
+
+impl Config {
+    /// The configuration file within the user's config directory.
+    pub fn user_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("pantry").join("config.toml"))
+    }
+
+    /// Loads the configuration from `path`, or if not given, from whichever of the user and recipe
+    /// directory configuration files exist. `recipe_dir` (if given) is used to find the latter.
+    pub fn load(path: Option<&Path>, recipe_dir: Option<&Path>) -> Result<Self, Error> {
+        if let Some(path) = path {
+            return read(path).and_then(|table| parse(path, table));
+        }
+
+        let mut config = toml::Table::new();
+        let mut source = PathBuf::new();
+        if let Some(path) = Self::user_path().filter(|p| p.exists()) {
+            merge(&mut config, read(&path)?);
+            source = path;
+        }
+
+        // The recipe directory may itself come from the user's configuration
+        let recipe_dir = recipe_dir
+            .map(PathBuf::from)
+            .or_else(|| config.get("recipe_dir")?.as_str().map(PathBuf::from))
+            .or_else(default_recipe_dir);
+        if let Some(path) = recipe_dir
+            .map(|dir| dir.join(FILE_NAME))
+            .filter(|p| p.exists())
+        {
+            merge(&mut config, read(&path)?);
+            source = path;
+        }
+
+        parse(&source, config)
+    }
+
+    /// The libraries to serve: the recipe directory (if given, or if no other libraries are)
+    /// becomes the primary library, followed by the others in alphabetical order.
+    pub fn libraries(&self) -> Result<Libraries, library::Error> {
+        Libraries::new(
+            self.recipe_dir()
+                .map(|dir| Library::new(library::DEFAULT_NAME, dir))
+                .into_iter()
+                .chain(
+                    self.libraries
+                        .iter()
+                        .map(|(name, path)| Library::new(name, path)),
+                ),
+        )
+    }
+
+    pub fn recipe_dir(&self) -> Option<PathBuf> {
+        self.recipe_dir
+            .clone()
+            .or_else(|| self.libraries.is_empty().then(default_recipe_dir).flatten())
+    }
+
+    pub fn to_toml(&self) -> Result<String, Error> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+}
+
+fn default_recipe_dir() -> Option<PathBuf> {
+    [
+        dirs::data_dir().map(|d| d.join("pantry")),
+        dirs::home_dir().map(|d| d.join(".pantry")),
+    ]
+    .into_iter()
+    .find(|d| d.is_some())
+    .flatten()
+}
+
+/// Merges `other` into `table`, recursing into tables present in both.
+fn merge(table: &mut toml::Table, other: toml::Table) {
+    for (key, value) in other {
+        match (table.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(value)) => {
+                merge(existing, value)
+            }
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
+
+fn parse(path: impl AsRef<Path>, table: toml::Table) -> Result<Config, Error> {
+    table
+        .try_into()
+        .map_err(|e| Error::Parse(PathBuf::from(path.as_ref()), e))
+}
+
+/// Reads the configuration file at `path`, validating it on its own, so that any errors are
+/// attributed to the right file before it's merged with the others.
+fn read(path: impl AsRef<Path>) -> Result<toml::Table, Error> {
+    let path = path.as_ref();
+    let table = fs::read_to_string(path)
+        .map_err(|e| Error::Io(PathBuf::from(path), e))?
+        .parse::<toml::Table>()
+        .map_err(|e| Error::Parse(PathBuf::from(path), e))?;
+
+    parse(path, table.clone())?;
+    Ok(table)
+}
@@ -0,0 +1,75 @@
+use std::sync::{Arc, Mutex};
+
+use smol::channel::{self, TrySendError};
+use tracing::warn;
+
+/// A change to the recipe collection, published to any open pages so they can refresh themselves.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Change {
+    /// The contents of the index changed, which may affect any search results.
+    Index,
+    /// The recipe with the given ID (its library and slug, e.g. `default/chili`) was updated.
+    /// Libraries may share slugs, so slugs alone don't identify a recipe.
+    Recipe(String),
+}
+
+impl Change {
+    /// The name of the Server-Sent Event for this change, as used by `hx-trigger="sse:<name>"`.
+    pub fn name(&self) -> String {
+        match self {
+            Self::Index => String::from("index"),
+            Self::Recipe(id) => format!("recipe-{id}"),
+        }
+    }
+}
+
+/// Fans out [`Change`]s to every subscriber. Rather than blocking the publisher, or silently
+/// missing changes, subscribers which fall behind are disconnected (and their pages reconnect).
+/// Closed subscribers are pruned on the next publish.
+#[derive(Clone, Debug, Default)]
+pub struct Broadcaster(Arc<Mutex<Vec<channel::Sender<Change>>>>);
+
+impl Broadcaster {
+    const BUFFER_SIZE: usize = 16;
+
+    pub fn publish(&self, change: Change) {
+        self.0
+            .lock()
+            .unwrap()
+            .retain(|tx| match tx.try_send(change.clone()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    warn!("Disconnecting a subscriber which fell behind on changes");
+                    false
+                }
+                Err(TrySendError::Closed(_)) => false,
+            });
+    }
+
+    pub fn subscribe(&self) -> channel::Receiver<Change> {
+        let (tx, rx) = channel::bounded(Self::BUFFER_SIZE);
+        self.0.lock().unwrap().push(tx);
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribers_which_fall_behind_are_disconnected() {
+        let events = Broadcaster::default();
+        let slow = events.subscribe();
+        let fast = events.subscribe();
+
+        for _ in 0..=Broadcaster::BUFFER_SIZE {
+            events.publish(Change::Index);
+            assert_eq!(fast.try_recv(), Ok(Change::Index));
+        }
+
+        assert_eq!(slow.len(), Broadcaster::BUFFER_SIZE);
+        assert!(slow.is_closed());
+        assert!(!fast.is_closed());
+    }
+}
@@ -1,7 +1,11 @@
+// https://notgull.net/new-smol-rs-subcrates/
 use std::{
+    collections::BTreeSet,
     path::{Path, PathBuf},
     pin::Pin,
-    sync::Arc,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
 };
 
 use notify::{Result, Watcher};
@@ -9,58 +13,170 @@ use smol::{
     channel,
     stream::{Stream, StreamExt},
 };
+use tracing::warn;
 
-#[derive(Clone)]
 pub struct AsyncWatcher {
-    _inner: Arc<notify::RecommendedWatcher>,
+    _inner: Box<dyn Watcher + Send>,
     channel: Pin<Box<smol::channel::Receiver<Event>>>,
 }
 
+/// How changes to the recipe directory are detected.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Backend {
+    /// The platform's native notification API (e.g. inotify), which is efficient but doesn't see
+    /// changes made over network filesystems or from outside a container's bind-mount.
+    Native,
+    /// Periodically rescans the directory tree, comparing file contents.
+    Poll(Duration),
+}
+
 pub enum Event {
     Update(Vec<PathBuf>),
     Remove(Vec<PathBuf>),
 }
 
-impl Event {
-    pub fn new(ev: notify::Event) -> Option<impl IntoIterator<Item = Self>> {
+/// Coalesces the raw notifications received within a single debounce window. Each path ends up in
+/// at most one of `updated` or `removed`, according to the last thing that happened to it.
+///
+/// Directories which appear (via creation or a rename) are expanded into updates for each recipe
+/// file within them. Directories which disappear are reported as-is, as their contents can no
+/// longer be listed; removing a directory's path removes every recipe beneath it from the index.
+#[derive(Debug, Default)]
+struct Batch {
+    updated: BTreeSet<PathBuf>,
+    removed: BTreeSet<PathBuf>,
+}
+
+impl Batch {
+    fn push(&mut self, ev: notify::Event) {
         use notify::{
-            event::{AccessKind, CreateKind, ModifyKind, RemoveKind, RenameMode},
+            event::{AccessKind, AccessMode, CreateKind, ModifyKind, RemoveKind, RenameMode},
             EventKind::{Access, Create, Modify, Remove},
         };
 
         match ev.kind {
-            Access(AccessKind::Close(_)) | Create(CreateKind::File) => {
-                Some(vec![Event::Update(ev.paths)])
+            // The polling backend only reports `Any` creations/removals, and `Data` modifications
+            Access(AccessKind::Close(AccessMode::Write))
+            | Create(CreateKind::File | CreateKind::Folder | CreateKind::Any)
+            | Modify(ModifyKind::Name(RenameMode::To) | ModifyKind::Data(_)) => {
+                ev.paths.into_iter().for_each(|p| self.update(p))
             }
-            Modify(ModifyKind::Name(RenameMode::Both)) => Some(vec![
-                Event::Remove(vec![ev.paths.first().unwrap().clone()]),
-                Event::Update(vec![ev.paths.get(1).unwrap().clone()]),
-            ]),
-            Remove(RemoveKind::File) => Some(vec![Event::Remove(ev.paths)]),
-            _ => None,
+            Modify(ModifyKind::Name(RenameMode::Both)) => {
+                let mut paths = ev.paths.into_iter();
+                if let (Some(from), Some(to)) = (paths.next(), paths.next()) {
+                    self.remove(from);
+                    self.update(to);
+                }
+            }
+            // The matching `To` half of the rename (if any) arrives as a separate event, and is
+            // treated as an update; a `From` without one means the file left the recipe directory
+            Modify(ModifyKind::Name(RenameMode::From))
+            | Remove(RemoveKind::File | RemoveKind::Folder | RemoveKind::Any) => {
+                ev.paths.into_iter().for_each(|p| self.remove(p))
+            }
+            _ => (),
         }
     }
+
+    fn push_result(&mut self, res: Result<notify::Event>) {
+        match res {
+            Ok(ev) => self.push(ev),
+            Err(e) => warn!("Filesystem watcher error: {e}"),
+        }
+    }
+
+    fn update(&mut self, path: PathBuf) {
+        if path.is_dir() {
+            crate::recipe::walk(&path).for_each(|p| self.update(p));
+        } else {
+            self.removed.remove(&path);
+            self.updated.insert(path);
+        }
+    }
+
+    fn remove(&mut self, path: PathBuf) {
+        self.updated.retain(|p| !p.starts_with(&path));
+        self.removed.insert(path);
+    }
+
+    fn into_events(self) -> impl Iterator<Item = Event> {
+        [
+            Event::Remove(Vec::from_iter(self.removed)),
+            Event::Update(Vec::from_iter(self.updated)),
+        ]
+        .into_iter()
+        .filter(|ev| match ev {
+            Event::Remove(paths) | Event::Update(paths) => !paths.is_empty(),
+        })
+    }
 }
 
-impl AsyncWatcher {
-    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
-        let (tx, rx) = channel::bounded(1);
+/// Collects raw notifications into batches, each spanning `window` from its first event, and
+/// forwards each batch as (at most) one removal and one update.
+fn debounce(
+    raw: mpsc::Receiver<Result<notify::Event>>,
+    tx: channel::Sender<Event>,
+    window: Duration,
+) {
+    while let Ok(first) = raw.recv() {
+        let mut batch = Batch::default();
+        batch.push_result(first);
 
-        let mut watcher = notify::RecommendedWatcher::new(
-            move |res: Result<notify::Event>| {
-                if let Ok(Some(events)) = res.map(Event::new) {
-                    for ev in events {
-                        tx.send_blocking(ev).unwrap();
-                    }
+        let deadline = Instant::now() + window;
+        let mut disconnected = false;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            match raw.recv_timeout(remaining) {
+                Ok(res) => batch.push_result(res),
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    disconnected = true;
+                    break;
                 }
-            },
-            notify::Config::default(),
-        )?;
+            }
+        }
+
+        for ev in batch.into_events() {
+            if tx.send_blocking(ev).is_err() {
+                return;
+            }
+        }
+
+        if disconnected {
+            return;
+        }
+    }
+}
 
+impl AsyncWatcher {
+    pub fn new(
+        path: impl AsRef<Path>,
+        backend: Backend,
+        debounce_window: Duration,
+    ) -> Result<Self> {
+        let (raw_tx, raw_rx) = mpsc::channel();
+        let (tx, rx) = channel::bounded(1);
+
+        let mut watcher: Box<dyn Watcher + Send> = match backend {
+            Backend::Native => Box::new(notify::RecommendedWatcher::new(
+                raw_tx,
+                notify::Config::default(),
+            )?),
+            Backend::Poll(interval) => Box::new(notify::PollWatcher::new(
+                raw_tx,
+                notify::Config::default()
+                    .with_poll_interval(interval)
+                    .with_compare_contents(true),
+            )?),
+        };
         watcher.watch(path.as_ref(), notify::RecursiveMode::Recursive)?;
 
+        thread::Builder::new()
+            .name(String::from("fswatch"))
+            .spawn(move || debounce(raw_rx, tx, debounce_window))
+            .map_err(notify::Error::io)?;
+
         Ok(Self {
-            _inner: Arc::new(watcher),
+            _inner: watcher,
             channel: Box::pin(rx),
         })
     }
@@ -76,3 +192,82 @@ impl Stream for AsyncWatcher {
         self.channel.poll_next(cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, EventKind, ModifyKind, RemoveKind};
+
+    fn event(kind: EventKind, path: &str) -> notify::Event {
+        notify::Event::new(kind).add_path(PathBuf::from(path))
+    }
+
+    fn modified(path: &str) -> notify::Event {
+        event(EventKind::Modify(ModifyKind::Data(DataChange::Any)), path)
+    }
+
+    fn removed(path: &str) -> notify::Event {
+        event(EventKind::Remove(RemoveKind::File), path)
+    }
+
+    fn paths(events: impl IntoIterator<Item = Event>) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let (mut updated, mut removed) = (Vec::new(), Vec::new());
+        for ev in events {
+            match ev {
+                Event::Update(paths) => updated.extend(paths),
+                Event::Remove(paths) => removed.extend(paths),
+            }
+        }
+        (updated, removed)
+    }
+
+    #[test]
+    fn update_then_removal_is_a_removal() {
+        let mut batch = Batch::default();
+        batch.push(modified("/recipes/chili.md"));
+        batch.push(removed("/recipes/chili.md"));
+
+        let (updated, removed) = paths(batch.into_events());
+        assert_eq!(updated, Vec::<PathBuf>::new());
+        assert_eq!(removed, [PathBuf::from("/recipes/chili.md")]);
+    }
+
+    #[test]
+    fn removal_then_update_is_an_update() {
+        let mut batch = Batch::default();
+        batch.push(removed("/recipes/chili.md"));
+        batch.push(event(
+            EventKind::Create(CreateKind::File),
+            "/recipes/chili.md",
+        ));
+
+        let (updated, removed) = paths(batch.into_events());
+        assert_eq!(updated, [PathBuf::from("/recipes/chili.md")]);
+        assert_eq!(removed, Vec::<PathBuf>::new());
+    }
+
+    #[test]
+    fn burst_is_one_batch() {
+        let (raw_tx, raw_rx) = mpsc::channel();
+        let (tx, rx) = channel::unbounded();
+        for path in ["/recipes/chili.md", "/recipes/soup.md", "/recipes/chili.md"] {
+            raw_tx.send(Ok(modified(path))).unwrap();
+        }
+        raw_tx.send(Ok(removed("/recipes/stew.md"))).unwrap();
+        drop(raw_tx);
+
+        debounce(raw_rx, tx, Duration::from_secs(60));
+
+        let events = std::iter::from_fn(|| rx.try_recv().ok()).collect::<Vec<_>>();
+        assert_eq!(events.len(), 2);
+        let (updated, removed) = paths(events);
+        assert_eq!(
+            updated,
+            [
+                PathBuf::from("/recipes/chili.md"),
+                PathBuf::from("/recipes/soup.md")
+            ]
+        );
+        assert_eq!(removed, [PathBuf::from("/recipes/stew.md")]);
+    }
+}
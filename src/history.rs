@@ -0,0 +1,239 @@
+use std::{
+    ffi::OsStr,
+    io,
+    path::{Path, PathBuf},
+};
+
+use smol::process::Command;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("git failed: {0}")]
+    Git(String),
+    #[error("invalid revision: {0:?}")]
+    InvalidRevision(String),
+    #[error("failed to run git: {0}")]
+    Io(#[from] io::Error),
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+// The fields and records of `git log` output are separated by the ASCII unit and record separators
+// (which don't appear in commit messages)
+const FIELD_SEPARATOR: char = '\x1f';
+const RECORD_SEPARATOR: char = '\x1e';
+const LOG_FORMAT: &str = "--format=%H%x1f%an%x1f%ad%x1f%s%x1f%b%x1e";
+
+/// A commit which touched a recipe.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Commit {
+    hash: String,
+    author: String,
+    date: String,
+    subject: String,
+    body: String,
+}
+
+impl Commit {
+    fn parse(record: &str) -> Option<Self> {
+        let mut fields = record.trim_start_matches('\n').split(FIELD_SEPARATOR);
+        Some(Self {
+            hash: String::from(fields.next()?),
+            author: String::from(fields.next()?),
+            date: String::from(fields.next()?),
+            subject: String::from(fields.next()?),
+            body: String::from(fields.next()?.trim()),
+        })
+    }
+
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    pub fn date(&self) -> &str {
+        &self.date
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(8)]
+    }
+
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LineKind {
+    Added,
+    Context,
+    Header,
+    Hunk,
+    Removed,
+}
+
+/// A line of a unified diff, classified for display.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DiffLine {
+    kind: LineKind,
+    text: String,
+}
+
+impl DiffLine {
+    fn parse(line: &str) -> Self {
+        let kind = if line.starts_with("+++") || line.starts_with("---") {
+            LineKind::Header
+        } else if line.starts_with("@@") {
+            LineKind::Hunk
+        } else if line.starts_with('+') {
+            LineKind::Added
+        } else if line.starts_with('-') {
+            LineKind::Removed
+        } else if line.starts_with(' ') || line.is_empty() {
+            LineKind::Context
+        } else {
+            LineKind::Header
+        };
+
+        Self {
+            kind,
+            text: String::from(line),
+        }
+    }
+
+    /// The CSS class used to style this line.
+    pub fn class(&self) -> &'static str {
+        match self.kind {
+            LineKind::Added => "added",
+            LineKind::Context => "context",
+            LineKind::Header => "header",
+            LineKind::Hunk => "hunk",
+            LineKind::Removed => "removed",
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/// A git repository containing (some of) the recipes, whose history can be browsed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Repository {
+    root: PathBuf,
+}
+
+impl Repository {
+    /// Finds the repository containing `dir`, if it's tracked by git (and git is installed).
+    pub async fn discover(dir: impl AsRef<Path>) -> Option<Self> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir.as_ref())
+            .args(["rev-parse", "--show-toplevel"])
+            .output()
+            .await
+            .ok()
+            .filter(|output| output.status.success())?;
+
+        let root = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (!root.is_empty()).then(|| Self {
+            root: PathBuf::from(root),
+        })
+    }
+
+    async fn git(&self, args: &[&OsStr]) -> Result<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.root)
+            .args(args)
+            .output()
+            .await?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(Error::Git(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ))
+        }
+    }
+
+    /// The commits which touched the file at `path`, most recent first, following renames.
+    pub async fn log(&self, path: impl AsRef<Path>) -> Result<Vec<Commit>> {
+        // Symlinks in the recipe directory's path would place it outside of the repository
+        let path = path.as_ref().canonicalize()?;
+        let output = self
+            .git(&[
+                OsStr::new("log"),
+                OsStr::new("--follow"),
+                OsStr::new("--date=format:%Y-%m-%d %H:%M"),
+                OsStr::new(LOG_FORMAT),
+                OsStr::new("--"),
+                path.as_os_str(),
+            ])
+            .await?;
+
+        Ok(output
+            .split(RECORD_SEPARATOR)
+            .filter(|record| !record.trim().is_empty())
+            .filter_map(Commit::parse)
+            .collect())
+    }
+
+    /// The changes to the file at `path` between the revisions `from` and `to`, or if `from` isn't
+    /// given, those made by `to` itself.
+    pub async fn diff(
+        &self,
+        path: impl AsRef<Path>,
+        from: Option<&str>,
+        to: &str,
+    ) -> Result<Vec<DiffLine>> {
+        let to = revision(to)?;
+        let path = path.as_ref().canonicalize()?;
+        let path = path.as_os_str();
+        let output = match from {
+            Some(from) => {
+                let from = revision(from)?;
+                self.git(&[
+                    OsStr::new("diff"),
+                    OsStr::new(from),
+                    OsStr::new(to),
+                    OsStr::new("--"),
+                    path,
+                ])
+                .await?
+            }
+            None => {
+                self.git(&[
+                    OsStr::new("show"),
+                    OsStr::new("--format="),
+                    OsStr::new(to),
+                    OsStr::new("--"),
+                    path,
+                ])
+                .await?
+            }
+        };
+
+        Ok(output.lines().map(DiffLine::parse).collect())
+    }
+}
+
+/// Only accepts (possibly abbreviated) commit hashes, so that revisions can't be mistaken for
+/// options or other syntax.
+fn revision(rev: &str) -> Result<&str> {
+    if (4..=64).contains(&rev.len()) && rev.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(rev)
+    } else {
+        Err(Error::InvalidRevision(String::from(rev)))
+    }
+}
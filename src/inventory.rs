@@ -0,0 +1,97 @@
+use std::{
+    fmt::{self, Display},
+    fs, io,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use time::{Date, OffsetDateTime};
+
+pub const FILE_NAME: &str = "_inventory.yaml";
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Quantity {
+    Count(f64),
+    Amount(String),
+}
+
+impl Quantity {
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Count(count) => *count <= 0.0,
+            Self::Amount(amount) => amount.trim().is_empty(),
+        }
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Count(count) => Display::fmt(count, f),
+            Self::Amount(amount) => Display::fmt(amount, f),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Item {
+    name: String,
+    #[serde(default)]
+    quantity: Option<Quantity>,
+    #[serde(default)]
+    expires: Option<Date>,
+}
+
+impl Item {
+    pub fn expires(&self) -> Option<&Date> {
+        self.expires.as_ref()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires
+            .is_some_and(|date| date < OffsetDateTime::now_utc().date())
+    }
+
+    pub fn is_in_stock(&self) -> bool {
+        !self.is_expired() && !self.quantity.as_ref().is_some_and(Quantity::is_empty)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn quantity(&self) -> Option<&Quantity> {
+        self.quantity.as_ref()
+    }
+}
+
+/// The household's stock of ingredients, as recorded in `_inventory.yaml` at the root of the recipe
+/// directory. The leading underscore keeps it out of the recipe index, while still allowing it to
+/// be synced and edited out-of-band like any recipe.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Inventory(Vec<Item>);
+
+impl Inventory {
+    pub fn load(recipe_dir: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::File::open(recipe_dir.as_ref().join(FILE_NAME)) {
+            Ok(f) => serde_yaml::from_reader(f)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn in_stock(&self) -> impl Iterator<Item = &Item> {
+        self.items().iter().filter(|item| item.is_in_stock())
+    }
+
+    pub fn items(&self) -> &Vec<Item> {
+        &self.0
+    }
+
+    pub fn is_inventory_file(recipe_dir: impl AsRef<Path>, path: impl AsRef<Path>) -> bool {
+        path.as_ref() == recipe_dir.as_ref().join(FILE_NAME)
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use time::Date;
+
+/// The directory (relative to a library) holding the journals. The leading underscore keeps it out
+/// of the recipe index.
+pub const DIR_NAME: &str = "_journal";
+
+/// A time a recipe was cooked.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub struct Entry {
+    date: Date,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rating: Option<u8>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    notes: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tweaks: Vec<String>,
+}
+
+impl Entry {
+    /// `rating` is from 1 to 5 stars.
+    pub fn new(
+        date: Date,
+        rating: Option<u8>,
+        notes: impl Into<String>,
+        tweaks: Vec<String>,
+    ) -> Self {
+        Self {
+            date,
+            rating,
+            notes: notes.into(),
+            tweaks,
+        }
+    }
+
+    pub fn date(&self) -> &Date {
+        &self.date
+    }
+
+    pub fn notes(&self) -> &str {
+        &self.notes
+    }
+
+    /// The rating as stars, e.g. "★★★☆☆". Ratings outside of 1 to 5 (as may be written by hand)
+    /// are ignored.
+    pub fn stars(&self) -> Option<String> {
+        self.rating
+            .filter(|rating| (1..=5).contains(rating))
+            .map(|rating| "★".repeat(rating.into()) + &"☆".repeat(5 - usize::from(rating)))
+    }
+
+    pub fn tweaks(&self) -> &[String] {
+        &self.tweaks
+    }
+}
+
+/// The log of each time a recipe was cooked, kept as a YAML list in `_journal`, at the recipe's
+/// path within its library (e.g. `_journal/mains/chili.md.yaml`). Entries are only ever appended,
+/// so the file can be synced and edited out-of-band like any recipe.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    /// The journal of the recipe at `recipe`, within the library at `library`.
+    pub fn for_recipe(library: impl AsRef<Path>, recipe: impl AsRef<Path>) -> Option<Self> {
+        let library = library.as_ref();
+        let relative = recipe.as_ref().strip_prefix(library).ok()?;
+        let mut path = library.join(DIR_NAME).join(relative);
+        path.as_mut_os_string().push(".yaml");
+        Some(Self { path })
+    }
+
+    /// The recipe whose journal is at `path`, if it is one.
+    pub fn recipe_path(library: impl AsRef<Path>, path: impl AsRef<Path>) -> Option<PathBuf> {
+        let library = library.as_ref();
+        let relative = path.as_ref().strip_prefix(library.join(DIR_NAME)).ok()?;
+        let recipe = relative.to_str()?.strip_suffix(".yaml")?;
+        Some(library.join(recipe))
+    }
+
+    /// The entries, in the order they were recorded.
+    pub fn entries(&self) -> io::Result<Vec<Entry>> {
+        match fs::File::open(&self.path) {
+            Ok(f) => serde_yaml::from_reader::<_, Option<Vec<Entry>>>(f)
+                .map(Option::unwrap_or_default)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Default::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn append(&self, entry: &Entry) -> io::Result<()> {
+        let yaml = serde_yaml::to_string(&[entry])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut f = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        // Each entry is written as a single-item list, which together form one list
+        f.write_all(yaml.trim_start_matches("---\n").as_bytes())
+    }
+}
@@ -0,0 +1,70 @@
+/// The languages Xapian can stem, by ISO 639-1 code and (Snowball) name.
+const LANGUAGES: &[(&str, &str)] = &[
+    ("ar", "arabic"),
+    ("ca", "catalan"),
+    ("da", "danish"),
+    ("de", "german"),
+    ("en", "english"),
+    ("es", "spanish"),
+    ("eu", "basque"),
+    ("fi", "finnish"),
+    ("fr", "french"),
+    ("ga", "irish"),
+    ("hu", "hungarian"),
+    ("hy", "armenian"),
+    ("id", "indonesian"),
+    ("it", "italian"),
+    ("lt", "lithuanian"),
+    ("nb", "norwegian"),
+    ("ne", "nepali"),
+    ("nl", "dutch"),
+    ("nn", "norwegian"),
+    ("no", "norwegian"),
+    ("pt", "portuguese"),
+    ("ro", "romanian"),
+    ("ru", "russian"),
+    ("sv", "swedish"),
+    ("ta", "tamil"),
+    ("tr", "turkish"),
+];
+
+/// A language recipes can be written in, which determines how their words are stemmed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Language {
+    code: &'static str,
+    name: &'static str,
+}
+
+impl Default for Language {
+    fn default() -> Self {
+        Self {
+            code: "en",
+            name: "english",
+        }
+    }
+}
+
+impl Language {
+    /// Looks up a language by its code (e.g. `de`, or `de-AT`) or name (e.g. `German`).
+    pub fn parse(s: impl AsRef<str>) -> Option<Self> {
+        let s = s.as_ref().trim().to_lowercase();
+        let code = s.split(['-', '_']).next().unwrap_or_default();
+
+        LANGUAGES
+            .iter()
+            .find(|(c, name)| *c == code || *name == s)
+            .map(|&(code, name)| Self { code, name })
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn stemmer(&self) -> xapian_rs::Stem {
+        xapian_rs::Stem::for_language(self.name)
+    }
+}
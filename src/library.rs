@@ -0,0 +1,105 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use thiserror::Error;
+
+/// The name given to the library in `--recipe-dir`.
+pub const DEFAULT_NAME: &str = "default";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("library {0:?} is defined more than once")]
+    Duplicate(String),
+    #[error("no recipe libraries are configured")]
+    Empty,
+    #[error("expected a library as NAME=PATH, got {0:?}")]
+    Syntax(String),
+}
+
+/// A named directory of recipes. Names are slugified, so they can be used in URLs and searches.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Library {
+    name: String,
+    path: PathBuf,
+}
+
+impl Library {
+    pub fn new(name: impl AsRef<str>, path: impl AsRef<Path>) -> Self {
+        Self {
+            name: slug::slugify(name),
+            path: PathBuf::from(path.as_ref()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether `path` lies within this library, and isn't hidden from indexing.
+    pub fn contains(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        path.starts_with(&self.path) && !crate::recipe::is_hidden_path(&self.path, path)
+    }
+}
+
+impl FromStr for Library {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((name, path)) if !name.trim().is_empty() && !path.trim().is_empty() => {
+                Ok(Self::new(name.trim(), path.trim()))
+            }
+            _ => Err(Error::Syntax(String::from(s))),
+        }
+    }
+}
+
+/// Every library served by this instance. The first is the primary library, which also holds the
+/// household's inventory, aisles, meal plans and shopping lists.
+#[derive(Clone, Debug)]
+pub struct Libraries(Arc<[Library]>);
+
+impl Libraries {
+    pub fn new(libraries: impl IntoIterator<Item = Library>) -> Result<Self, Error> {
+        let libraries = libraries.into_iter().collect::<Vec<_>>();
+        if libraries.is_empty() {
+            return Err(Error::Empty);
+        }
+
+        for (i, library) in libraries.iter().enumerate() {
+            if libraries[..i].iter().any(|l| l.name == library.name) {
+                return Err(Error::Duplicate(library.name.clone()));
+            }
+        }
+
+        Ok(Self(libraries.into()))
+    }
+
+    /// Finds the library containing `path`, preferring the most specific if libraries are nested.
+    pub fn find(&self, path: impl AsRef<Path>) -> Option<&Library> {
+        let path = path.as_ref();
+        self.iter()
+            .filter(|library| path.starts_with(&library.path))
+            .max_by_key(|library| library.path.components().count())
+    }
+
+    pub fn get(&self, name: impl AsRef<str>) -> Option<&Library> {
+        self.iter().find(|library| library.name == name.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Library> {
+        self.0.iter()
+    }
+
+    pub fn primary(&self) -> &Library {
+        &self.0[0]
+    }
+}
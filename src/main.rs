@@ -1,12 +1,24 @@
 mod assets;
+mod config;
+mod events;
 mod fswatch;
+mod history;
+mod inventory;
+mod journal;
+mod language;
+mod library;
 mod markdown;
+mod mealplan;
 mod recipe;
 mod routes;
 mod search;
+mod shopping;
+mod snapshots;
+mod steps;
+mod tags;
 mod templates;
 
-use std::{io, net::SocketAddr, path::PathBuf, sync::Arc};
+use std::{io, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 
 use axum::Router;
 use clap::Parser;
@@ -15,13 +27,87 @@ use smol::{net::TcpListener, stream::StreamExt};
 use smol_macros::{main, Executor};
 use tracing::info;
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
+use url::Url;
 
+// Command-line arguments (and their environment variables) override the configuration file
 #[derive(Parser)]
 struct Args {
-    #[arg(long, short, env = "PANTRY_ADDRESS", default_value = "127.0.0.1:3000")]
-    listen_on: SocketAddr,
+    /// Read the configuration from this file, rather than discovering it
+    #[arg(long, short, env = "PANTRY_CONFIG")]
+    config: Option<PathBuf>,
+    /// Print the effective configuration, and exit
+    #[arg(long)]
+    print_config: bool,
+    #[arg(long, short, env = "PANTRY_ADDRESS")]
+    listen_on: Option<SocketAddr>,
+    /// The public URL Pantry is reachable at
+    #[arg(long, env = "PANTRY_BASE_URL")]
+    base_url: Option<Url>,
     #[arg(long, short = 'd', env = "PANTRY_RECIPE_DIR")]
     recipe_dir: Option<PathBuf>,
+    /// Additional recipe libraries, as NAME=PATH (may be repeated)
+    #[arg(long = "library", env = "PANTRY_LIBRARIES", value_delimiter = ',')]
+    libraries: Vec<library::Library>,
+    /// How many search results to show per page
+    #[arg(long, env = "PANTRY_PAGE_SIZE")]
+    page_size: Option<u32>,
+    /// The language used to stem words when indexing and searching
+    #[arg(long, env = "PANTRY_LANGUAGE")]
+    language: Option<String>,
+    /// How long to collect filesystem events for, before reindexing the affected recipes
+    #[arg(long, env = "PANTRY_DEBOUNCE_MS")]
+    debounce_ms: Option<u64>,
+    /// How often to check for changes the filesystem watcher missed, in seconds (0 to disable)
+    #[arg(long, env = "PANTRY_RECONCILE_INTERVAL")]
+    reconcile_interval: Option<u64>,
+    /// How to detect changes to the recipe directory
+    #[arg(long, env = "PANTRY_WATCHER", value_enum)]
+    watcher: Option<config::WatcherBackend>,
+    /// How often the polling watcher scans the recipe directory, in seconds
+    #[arg(long, env = "PANTRY_POLL_INTERVAL")]
+    poll_interval: Option<u64>,
+}
+
+impl Args {
+    /// Loads the configuration file, and applies any overrides from the command-line.
+    fn config(&self) -> Result<config::Config, config::Error> {
+        let mut config = config::Config::load(self.config.as_deref(), self.recipe_dir.as_deref())?;
+
+        config.listen_on = self.listen_on.unwrap_or(config.listen_on);
+        config.base_url = self.base_url.clone().or(config.base_url);
+        config.recipe_dir = self.recipe_dir.clone().or(config.recipe_dir);
+        for library in &self.libraries {
+            config
+                .libraries
+                .insert(String::from(library.name()), PathBuf::from(library.path()));
+        }
+
+        let search = &mut config.search;
+        search.page_size = self.page_size.unwrap_or(search.page_size);
+        if let Some(ref language) = self.language {
+            search.language = language.clone();
+        }
+
+        let watcher = &mut config.watcher;
+        watcher.backend = self.watcher.unwrap_or(watcher.backend);
+        watcher.poll_interval = self.poll_interval.unwrap_or(watcher.poll_interval);
+        watcher.debounce_ms = self.debounce_ms.unwrap_or(watcher.debounce_ms);
+        watcher.reconcile_interval = self
+            .reconcile_interval
+            .unwrap_or(watcher.reconcile_interval);
+
+        config.recipe_dir = config.recipe_dir();
+        Ok(config)
+    }
+}
+
+fn watcher_backend(config: &config::Watcher) -> fswatch::Backend {
+    match config.backend {
+        config::WatcherBackend::Native => fswatch::Backend::Native,
+        config::WatcherBackend::Poll => {
+            fswatch::Backend::Poll(Duration::from_secs(config.poll_interval.max(1)))
+        }
+    }
 }
 
 async fn web_server(
@@ -34,17 +120,6 @@ async fn web_server(
     smol_axum::serve(ex.clone(), listener, service).await
 }
 
-fn resolve_recipe_dir(args: &Args) -> Option<PathBuf> {
-    [
-        args.recipe_dir.clone(),
-        dirs::data_dir().map(|d| d.join("pantry")),
-        dirs::home_dir().map(|d| d.join(".pantry")),
-    ]
-    .into_iter()
-    .find(|d| d.is_some())
-    .flatten()
-}
-
 #[apply(main)]
 async fn main(ex: &Arc<Executor<'_>>) -> anyhow::Result<()> {
     let logger = FmtSubscriber::builder()
@@ -57,15 +132,44 @@ async fn main(ex: &Arc<Executor<'_>>) -> anyhow::Result<()> {
     tracing::subscriber::set_global_default(logger)?;
 
     let args = Args::parse();
-    let recipe_dir = resolve_recipe_dir(&args)
-        .expect("Unable to find data directory, please specify --recipe-dir!");
+    let config = args.config()?;
+    if args.print_config {
+        print!("{}", config.to_toml()?);
+        return Ok(());
+    }
 
-    let app_state = routes::AppState::new(&recipe_dir);
+    anyhow::ensure!(
+        language::Language::parse(&config.search.language).is_some(),
+        "Unsupported language: {}",
+        config.search.language
+    );
 
-    let _reloader = {
+    let libraries = match config.libraries() {
+        Err(library::Error::Empty) => {
+            anyhow::bail!("Unable to find data directory, please specify --recipe-dir!")
+        }
+        res => res?,
+    };
+
+    let app_state = routes::AppState::new(libraries.clone(), config.search.clone())
+        .await
+        .with_base_url(config.base_url.clone());
+
+    // Each library is watched independently, as they may live on different filesystems
+    let mut reloaders = Vec::new();
+    for library in libraries.iter() {
+        info!(
+            "Serving library {} from {:?}",
+            library.name(),
+            library.path()
+        );
         let app_state = app_state.clone();
-        let mut watcher = fswatch::AsyncWatcher::new(&recipe_dir)?;
-        ex.spawn(async move {
+        let mut watcher = fswatch::AsyncWatcher::new(
+            library.path(),
+            watcher_backend(&config.watcher),
+            Duration::from_millis(config.watcher.debounce_ms),
+        )?;
+        reloaders.push(ex.spawn(async move {
             use fswatch::Event;
             while let Some(ev) = watcher.next().await {
                 match ev {
@@ -73,12 +177,26 @@ async fn main(ex: &Arc<Executor<'_>>) -> anyhow::Result<()> {
                     Event::Remove(paths) => app_state.remove(paths).await,
                 }
             }
+        }));
+    }
+
+    let reconcile_interval = config.watcher.reconcile_interval;
+    let _reconciler = (reconcile_interval > 0).then(|| {
+        let app_state = app_state.clone();
+        let mut interval = smol::Timer::interval(Duration::from_secs(reconcile_interval));
+        ex.spawn(async move {
+            while interval.next().await.is_some() {
+                app_state.reconcile().await;
+            }
         })
-    };
+    });
 
     // Perform an initial load of the dataset
     app_state.reload(None).await;
-    Ok(web_server(ex, &args.listen_on, routes::router(app_state)).await?)
+    if let Some(ref base_url) = config.base_url {
+        info!("Serving at {base_url}");
+    }
+    Ok(web_server(ex, &config.listen_on, routes::router(app_state)).await?)
 }
 
 // https://notgull.net/new-smol-rs-subcrates/
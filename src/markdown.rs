@@ -3,6 +3,8 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+const TRANSCLUSION_MARKER: &str = "%%transclude:";
+
 #[derive(Default)]
 struct HeadingTagData {
     last_level: u8,
@@ -84,19 +86,156 @@ impl Parser {
             )
             .build();
 
-        comrak::markdown_to_html_with_plugins(mkd.as_ref(), &self.options, &plugins)
+        let mkd = Self::mark_transclusions(mkd.as_ref());
+        let html = comrak::markdown_to_html_with_plugins(&mkd, &self.options, &plugins);
+        Self::render_transclusions(html)
+    }
+
+    /// Replaces `![[Recipe Name]]` lines with a standalone paragraph containing a marker, which is
+    /// later rendered as a transclusion placeholder by [`Parser::render_transclusions`]. Lines
+    /// within fenced or indented code blocks are left as they are.
+    fn mark_transclusions(mkd: &str) -> String {
+        let mut fence: Option<&str> = None;
+        let mut indented_code = false;
+        let mut blank = true;
+
+        mkd.lines()
+            .map(|line| {
+                if let Some(open) = fence {
+                    // A closing fence is at least as long as the opening one, and stands alone
+                    if code_fence(line)
+                        .is_some_and(|close| close.starts_with(open) && line.trim() == close)
+                    {
+                        fence = None;
+                        blank = false;
+                    }
+                    return String::from(line);
+                }
+                if let Some(open) = code_fence(line) {
+                    fence = Some(open);
+                    indented_code = false;
+                    return String::from(line);
+                }
+
+                // Indented code can't interrupt a paragraph, so must follow a blank line
+                let indented = line.starts_with("    ") || line.starts_with('\t');
+                indented_code = indented && (blank || indented_code);
+                blank = line.trim().is_empty();
+                if indented_code {
+                    return String::from(line);
+                }
+
+                let name = line
+                    .trim()
+                    .strip_prefix('!')
+                    .filter(|link| link.starts_with("[["))
+                    .and_then(wiki_link);
+
+                match name {
+                    Some(name) => {
+                        format!("\n{TRANSCLUSION_MARKER}{}:{name}%%\n", slug::slugify(&name))
+                    }
+                    None => String::from(line),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn render_transclusions(mut html: String) -> String {
+        let open = format!("<p>{TRANSCLUSION_MARKER}");
+        while let Some(start) = html.find(&open) {
+            let Some(len) = html[start..].find("%%</p>") else {
+                break;
+            };
+
+            let marker = &html[start + open.len()..start + len];
+            let (slug, name) = marker.split_once(':').unwrap_or((marker, marker));
+            let placeholder = format!(
+                r#"<div class="transclusion" data-recipe="{slug}"><a href="/recipe/{slug}">{name}</a></div>"#
+            );
+            html.replace_range(start..start + len + "%%</p>".len(), &placeholder);
+        }
+        html
     }
 
     fn resolve_broken_link(link: comrak::BrokenLinkReference) -> Option<comrak::ResolvedReference> {
-        let url = format!("/recipe/{}", slug::slugify(link.normalized));
+        let url = recipe_url(link.normalized);
         let title = String::from(link.original.trim_start_matches("[").trim_end_matches("]"));
 
         Some(comrak::ResolvedReference { url, title })
     }
 }
 
+/// The fence (a run of at least three backticks or tildes) opening or closing a fenced code block
+/// on `line`, if any. Fences may be indented by up to three spaces.
+fn code_fence(line: &str) -> Option<&str> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let line = line.get(indent..).filter(|_| indent <= 3)?;
+    let fence = line.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let len = line.len() - line.trim_start_matches(fence).len();
+    (len >= 3).then(|| &line[..len])
+}
+
+/// Resolves a recipe name, as written in a `[Recipe Name]` reference, to the URL of that recipe.
+pub fn recipe_url(name: impl AsRef<str>) -> String {
+    format!("/recipe/{}", slug::slugify(name))
+}
+
+/// Extracts the recipe name from a `[Recipe Name]` or `[[Recipe Name]]` reference, if `text`
+/// consists solely of one.
+pub fn wiki_link(text: impl AsRef<str>) -> Option<String> {
+    let text = text.as_ref().trim();
+    text.strip_prefix('[')
+        .and_then(|t| t.strip_suffix(']'))
+        .map(|t| t.trim_start_matches('[').trim_end_matches(']').trim())
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    const PLACEHOLDER: &str =
+        r#"<div class="transclusion" data-recipe="chili"><a href="/recipe/chili">Chili</a></div>"#;
+
+    #[test]
+    fn transclusions_render_placeholders() {
+        let html = Parser::default().parse("Serve over rice.\n![[Chili]]\nEnjoy!\n");
+        assert!(html.contains(PLACEHOLDER));
+        assert!(html.contains("<p>Serve over rice.</p>"));
+        assert!(html.contains("<p>Enjoy!</p>"));
+    }
+
+    #[test]
+    fn transclusions_in_code_are_left_alone() {
+        let parser = Parser::default();
+        for mkd in [
+            "```\n![[Chili]]\n```\n",
+            "~~~~ markdown\n![[Chili]]\n~~~\n![[Chili]]\n~~~~\n",
+            "Example:\n\n    ![[Chili]]\n",
+            "Example:\n\n\t![[Chili]]\n",
+        ] {
+            let html = parser.parse(mkd);
+            assert!(!html.contains("transclusion"), "{mkd:?}: {html}");
+            assert!(html.contains("![[Chili]]"), "{mkd:?}: {html}");
+        }
+    }
+
+    #[test]
+    fn transclusions_after_code_are_rendered() {
+        let parser = Parser::default();
+        for mkd in [
+            "```\n![[Soup]]\n```\n![[Chili]]\n",
+            "    let x = 1;\n\n![[Chili]]\n",
+            // Indented lines continuing a paragraph aren't code
+            "Serve with\n    ![[Chili]]\n",
+        ] {
+            let html = parser.parse(mkd);
+            assert!(html.contains(PLACEHOLDER), "{mkd:?}: {html}");
+        }
+    }
 
     #[test]
     fn parse_recipe() {
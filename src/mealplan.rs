@@ -0,0 +1,222 @@
+use crate::markdown;
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+pub const DIR_NAME: &str = "_meal-plans";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Meal {
+    title: String,
+    slug: Option<String>,
+}
+
+impl Meal {
+    fn parse(text: &str) -> Self {
+        match markdown::wiki_link(text) {
+            Some(title) => Self {
+                slug: Some(slug::slugify(&title)),
+                title,
+            },
+            None => Self {
+                title: String::from(text.trim()),
+                slug: None,
+            },
+        }
+    }
+
+    pub fn slug(&self) -> Option<&str> {
+        self.slug.as_deref()
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn url(&self) -> Option<String> {
+        self.slug.as_ref().map(markdown::recipe_url)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Day {
+    name: String,
+    meals: Vec<Meal>,
+}
+
+impl Day {
+    pub fn meals(&self) -> &Vec<Meal> {
+        &self.meals
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A week of meals, stored as a Markdown file in the `_meal-plans` directory. Each `##` heading
+/// names a day, followed by a list of meals; meals written as `[Recipe Name]` or `[[Recipe Name]]`
+/// link to the corresponding recipe.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MealPlan {
+    week: String,
+    days: Vec<Day>,
+}
+
+impl MealPlan {
+    fn dir(recipe_dir: impl AsRef<Path>) -> PathBuf {
+        recipe_dir.as_ref().join(DIR_NAME)
+    }
+
+    pub fn parse(week: impl Into<String>, s: impl AsRef<str>) -> Self {
+        let mut days: Vec<Day> = Vec::new();
+
+        for line in s.as_ref().lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix("## ") {
+                days.push(Day {
+                    name: String::from(name.trim()),
+                    ..Default::default()
+                });
+            } else if let Some(item) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+                if let Some(day) = days.last_mut() {
+                    day.meals.push(Meal::parse(item));
+                }
+            }
+        }
+
+        Self {
+            week: week.into(),
+            days,
+        }
+    }
+
+    pub fn load(recipe_dir: impl AsRef<Path>, week: impl AsRef<str>) -> io::Result<Self> {
+        let week = week.as_ref();
+        Self::weeks(&recipe_dir)?
+            .into_iter()
+            .find(|w| w == week)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+            .and_then(|week| {
+                let path = Self::dir(&recipe_dir).join(format!("{week}.md"));
+                Ok(Self::parse(week, fs::read_to_string(path)?))
+            })
+    }
+
+    /// Lists the available weeks, most recent first.
+    pub fn weeks(recipe_dir: impl AsRef<Path>) -> io::Result<Vec<String>> {
+        let mut weeks = match fs::read_dir(Self::dir(recipe_dir)) {
+            Ok(entries) => entries
+                .filter_map(|res| res.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+                .filter_map(|path| {
+                    path.file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .filter(|stem| !stem.starts_with('.'))
+                        .map(String::from)
+                })
+                .collect::<Vec<_>>(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        weeks.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(weeks)
+    }
+
+    pub fn days(&self) -> &Vec<Day> {
+        &self.days
+    }
+
+    pub fn shopping_list_url(&self) -> String {
+        let query = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(self.slugs().map(|slug| ("recipe", slug)))
+            .finish();
+        format!("/shopping-list?{query}")
+    }
+
+    pub fn slugs(&self) -> impl Iterator<Item = &str> {
+        self.days
+            .iter()
+            .flat_map(|day| day.meals.iter())
+            .filter_map(Meal::slug)
+    }
+
+    pub fn week(&self) -> &str {
+        &self.week
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEEK: &str = "# Week of November 3rd
+
+Some notes, which aren't meals.
+
+- Not a meal either
+
+## Monday
+
+- [Chili]
+- Leftovers
+
+## Tuesday
+* [[Pad Thai]]
+  - [ Tacos ]
+
+## Wednesday
+";
+
+    fn meals(day: &Day) -> Vec<(&str, Option<&str>)> {
+        day.meals()
+            .iter()
+            .map(|meal| (meal.title(), meal.slug()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_days_and_meals() {
+        let plan = MealPlan::parse("2024-11-03", WEEK);
+        assert_eq!(plan.week(), "2024-11-03");
+
+        let days = plan.days();
+        assert_eq!(
+            days.iter().map(Day::name).collect::<Vec<_>>(),
+            ["Monday", "Tuesday", "Wednesday"]
+        );
+        assert_eq!(
+            meals(&days[0]),
+            [("Chili", Some("chili")), ("Leftovers", None)]
+        );
+        assert_eq!(
+            meals(&days[1]),
+            [("Pad Thai", Some("pad-thai")), ("Tacos", Some("tacos"))]
+        );
+        assert!(days[2].meals().is_empty());
+    }
+
+    #[test]
+    fn linked_meals_make_a_shopping_list() {
+        let plan = MealPlan::parse("2024-11-03", WEEK);
+        assert_eq!(
+            plan.slugs().collect::<Vec<_>>(),
+            ["chili", "pad-thai", "tacos"]
+        );
+        assert_eq!(
+            plan.shopping_list_url(),
+            "/shopping-list?recipe=chili&recipe=pad-thai&recipe=tacos"
+        );
+        assert_eq!(
+            plan.days()[0].meals()[0].url().as_deref(),
+            Some("/recipe/chili")
+        );
+    }
+
+    #[test]
+    fn parse_empty_week() {
+        assert!(MealPlan::parse("2024-11-10", "").days().is_empty());
+    }
+}
@@ -1,9 +1,10 @@
-use crate::markdown;
+use crate::{markdown, steps::Step};
 
 use std::{
     collections::{BTreeMap, BTreeSet},
     fmt::{self, Display},
-    io::{self, Read},
+    fs,
+    io::{self, Read, Write},
     path::{Path, PathBuf},
 };
 
@@ -16,7 +17,7 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 use yaml_front_matter::YamlFrontMatter;
 
-#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
 pub struct Category(String);
 
 impl AsRef<str> for Category {
@@ -77,7 +78,80 @@ impl Source {
     }
 }
 
-#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+/// The value of a custom frontmatter field, as text. Lists keep each of their (scalar) items, while
+/// nested mappings are ignored.
+#[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(transparent)]
+pub struct Field(Vec<String>);
+
+impl Field {
+    pub fn values(&self) -> &Vec<String> {
+        &self.0
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0.join(", "), f)
+    }
+}
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Scalar {
+            Bool(bool),
+            Int(i64),
+            Float(f64),
+            Text(String),
+        }
+
+        impl Scalar {
+            fn into_string(self) -> String {
+                match self {
+                    Self::Bool(b) => b.to_string(),
+                    Self::Int(i) => i.to_string(),
+                    Self::Float(f) => f.to_string(),
+                    Self::Text(s) => s,
+                }
+            }
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            One(Scalar),
+            Many(Vec<Scalar>),
+            Other(serde::de::IgnoredAny),
+        }
+
+        Ok(match Raw::deserialize(deserializer)? {
+            Raw::One(scalar) => Self(vec![scalar.into_string()]),
+            Raw::Many(scalars) => Self(scalars.into_iter().map(Scalar::into_string).collect()),
+            Raw::Other(_) => Self::default(),
+        })
+    }
+}
+
+// Ratings and favorites are hand-written, so values which don't make sense (e.g. `rating: great`)
+// are ignored, rather than discarding the rest of the frontmatter
+fn deserialize_rating<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<u8>, D::Error> {
+    Ok(Field::deserialize(d)?
+        .values()
+        .first()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|rating| (1..=5).contains(rating)))
+}
+
+fn deserialize_favorite<'de, D: serde::Deserializer<'de>>(d: D) -> Result<bool, D::Error> {
+    Ok(Field::deserialize(d)?
+        .values()
+        .first()
+        .is_some_and(|value| ["true", "yes"].contains(&value.trim().to_lowercase().as_str())))
+}
+
+#[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
 pub struct MetaData {
     title: String,
     category: Category,
@@ -85,6 +159,17 @@ pub struct MetaData {
     sources: Vec<Source>,
     #[serde(default)]
     tags: BTreeSet<String>,
+    /// The language the recipe is written in, if not the configured default
+    #[serde(default)]
+    lang: Option<String>,
+    /// From 1 to 5 stars
+    #[serde(default, deserialize_with = "deserialize_rating")]
+    rating: Option<u8>,
+    #[serde(default, deserialize_with = "deserialize_favorite")]
+    favorite: bool,
+    /// Any other frontmatter, which may be configured as searchable via `search.fields`
+    #[serde(flatten)]
+    fields: BTreeMap<String, Field>,
 }
 
 impl MetaData {
@@ -92,6 +177,26 @@ impl MetaData {
         &self.category
     }
 
+    pub fn field(&self, name: impl AsRef<str>) -> Option<&Field> {
+        self.fields.get(name.as_ref())
+    }
+
+    pub fn fields(&self) -> &BTreeMap<String, Field> {
+        &self.fields
+    }
+
+    pub fn is_favorite(&self) -> bool {
+        self.favorite
+    }
+
+    pub fn lang(&self) -> Option<&str> {
+        self.lang.as_deref()
+    }
+
+    pub fn rating(&self) -> Option<u8> {
+        self.rating
+    }
+
     pub fn slug(&self) -> String {
         slug::slugify(self.title())
     }
@@ -109,10 +214,32 @@ impl MetaData {
     }
 }
 
+/// A list within a primary section of a recipe (e.g. `Ingredients`), optionally named by the `h3`
+/// heading preceding it. Items are kept as HTML.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Section {
+    name: Option<String>,
+    items: Vec<String>,
+}
+
+impl Section {
+    pub fn items(&self) -> &Vec<String> {
+        &self.items
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
 #[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
 pub struct Recipe {
     metadata: Option<MetaData>,
     contents: String,
+    #[serde(default)]
+    library: Option<String>,
+    #[serde(default)]
+    path: Option<PathBuf>,
 }
 
 impl From<SchemaOrgRecipe> for Recipe {
@@ -120,8 +247,7 @@ impl From<SchemaOrgRecipe> for Recipe {
         let metadata = MetaData {
             title: recipe.name().clone(),
             category: String::from("Imported").into(),
-            sources: Default::default(),
-            tags: Default::default(),
+            ..Default::default()
         }
         .into();
 
@@ -209,27 +335,6 @@ impl Recipe {
         Ok(Self::parse(input))
     }
 
-    pub fn load_all(path: impl AsRef<Path>) -> impl Iterator<Item = (PathBuf, Self)> {
-        walkdir::WalkDir::new(path)
-            .follow_links(false)
-            .same_file_system(true)
-            .into_iter()
-            .filter_entry(|entry| !is_hidden(entry))
-            .filter_map(|res| {
-                res.ok().and_then(|entry| {
-                    let path = entry.path();
-                    let file_name = entry.file_name().to_string_lossy();
-                    if file_name.starts_with('_') || path.is_dir() {
-                        None
-                    } else {
-                        std::fs::File::open(path)
-                            .and_then(|f| Self::from_reader(f).map(|r| (PathBuf::from(path), r)))
-                            .ok()
-                    }
-                })
-            })
-    }
-
     fn as_html(&self) -> scraper::Html {
         scraper::Html::parse_fragment(self.contents())
     }
@@ -258,6 +363,132 @@ impl Recipe {
         }
     }
 
+    fn list_items(&self, selector: impl AsRef<str>) -> Vec<String> {
+        let html = self.as_html();
+        if let Some(map) = parse_sectioned_list(&html, selector.as_ref()) {
+            map.into_values().flatten().collect()
+        } else {
+            parse_unified_list(&html, selector.as_ref()).unwrap_or_default()
+        }
+    }
+
+    /// The lists within the primary section identified by `id`, in document order.
+    pub fn sections(&self, id: impl AsRef<str>) -> Vec<Section> {
+        let id = id.as_ref();
+        let html = self.as_html();
+        let sel_li = scraper::Selector::parse("li").unwrap();
+        let sel_unified = scraper::Selector::parse(&format!("h2#{id} + ul")).unwrap();
+        let sel_headings = scraper::Selector::parse(&format!("h3.{id}")).unwrap();
+
+        let items = |list: scraper::ElementRef| {
+            list.select(&sel_li)
+                .map(|li| li.inner_html().trim().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        if let Some(list) = html.select(&sel_unified).next() {
+            return vec![Section {
+                name: None,
+                items: items(list),
+            }];
+        }
+
+        html.select(&sel_headings)
+            .filter_map(|heading| {
+                let list = heading
+                    .next_siblings()
+                    .find_map(scraper::ElementRef::wrap)
+                    .filter(|el| el.value().name() == "ul")?;
+
+                Some(Section {
+                    name: Some(heading.text().collect::<String>()),
+                    items: items(list),
+                })
+            })
+            .collect()
+    }
+
+    /// Renders this recipe's ingredients and directions (plus anything it embeds itself) for
+    /// embedding within another recipe.
+    pub fn embedded_html(&self) -> String {
+        let title = self.title().unwrap_or("Unknown");
+        let url = self.url().unwrap_or_default();
+
+        let mut html = format!(
+            r#"<details class="transclusion" open><summary><a href="{url}">{}</a></summary>"#,
+            escape_html(title)
+        );
+
+        for (id, heading) in [("ingredients", "Ingredients"), ("directions", "Directions")] {
+            let sections = self.sections(id);
+            if sections.is_empty() {
+                continue;
+            }
+
+            html.push_str(&format!("<h4>{heading}</h4>"));
+            for section in sections {
+                if let Some(name) = section.name() {
+                    html.push_str(&format!("<h5>{}</h5>", escape_html(name)));
+                }
+                html.push_str("<ul>");
+                for item in section.items() {
+                    html.push_str(&format!("<li>{item}</li>"));
+                }
+                html.push_str("</ul>");
+            }
+        }
+
+        let document = self.as_html();
+        for nested in document
+            .root_element()
+            .children()
+            .filter_map(scraper::ElementRef::wrap)
+            .filter(|el| {
+                // Embedded recipes, and notices of those which would embed themselves
+                matches!(el.value().name(), "details" | "p")
+                    && el.value().classes().any(|c| c == "transclusion")
+            })
+        {
+            html.push_str(&nested.html());
+        }
+
+        html.push_str("</details>");
+        html
+    }
+
+    /// The slugs of recipes embedded within this one via `![[Recipe Name]]`, which have not yet
+    /// been replaced by [`Recipe::transclude`].
+    pub fn transclusions(&self) -> Vec<String> {
+        let sel_transclusion = scraper::Selector::parse("div.transclusion[data-recipe]").unwrap();
+        let mut slugs = Vec::new();
+
+        for slug in self
+            .as_html()
+            .select(&sel_transclusion)
+            .filter_map(|el| el.attr("data-recipe"))
+        {
+            if !slugs.iter().any(|s| s == slug) {
+                slugs.push(String::from(slug));
+            }
+        }
+
+        slugs
+    }
+
+    /// Replaces the placeholder(s) for the embedded recipe `slug` with `html`. Only whole
+    /// placeholders, as rendered by [`markdown::Parser`], are replaced.
+    pub fn transclude(&mut self, slug: impl AsRef<str>, html: impl AsRef<str>) {
+        let slug = regex::escape(slug.as_ref());
+        let placeholder = regex::Regex::new(&format!(
+            r#"<div class="transclusion" data-recipe="{slug}"><a href="/recipe/{slug}">[^<]*</a></div>"#
+        ))
+        .unwrap();
+
+        self.contents = placeholder
+            .replace_all(&self.contents, regex::NoExpand(html.as_ref()))
+            .into_owned();
+    }
+
     pub fn category(&self) -> Option<&Category> {
         self.metadata().map(|md| md.category())
     }
@@ -270,10 +501,70 @@ impl Recipe {
         self.list_field("ingredients")
     }
 
+    pub fn ingredient_list(&self) -> Vec<String> {
+        self.list_items("ingredients")
+    }
+
+    /// The slugs of all recipes referenced by this one, via `[Recipe Name]` links.
+    pub fn links(&self) -> BTreeSet<String> {
+        let sel_a = scraper::Selector::parse(r#"a[href^="/recipe/"]"#).unwrap();
+
+        self.as_html()
+            .select(&sel_a)
+            .filter_map(|a| a.attr("href"))
+            .filter_map(|href| href.strip_prefix("/recipe/"))
+            .map(String::from)
+            .collect()
+    }
+
+    /// Points this recipe's links to the recipe `link` (as returned by [`Recipe::links`]) at `url`.
+    pub fn relink(&mut self, link: impl AsRef<str>, url: impl AsRef<str>) {
+        let from = format!(r#"href="/recipe/{}""#, link.as_ref());
+        let to = format!(r#"href="{}""#, url.as_ref());
+        self.contents = self.contents.replace(&from, &to);
+    }
+
+    /// Identifies this recipe across libraries, as `library/slug`.
+    pub fn id(&self) -> Option<String> {
+        let slug = self.metadata().map(|md| md.slug())?;
+        Some(match self.library() {
+            Some(library) => format!("{library}/{slug}"),
+            None => slug,
+        })
+    }
+
+    /// The name of the library this recipe was loaded from, if any.
+    pub fn library(&self) -> Option<&str> {
+        self.library.as_deref()
+    }
+
     pub fn metadata(&self) -> Option<&MetaData> {
         self.metadata.as_ref()
     }
 
+    /// The file this recipe was loaded from, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn url(&self) -> Option<String> {
+        self.id().map(|id| format!("/recipe/{id}"))
+    }
+
+    pub fn with_library(self, library: impl Into<String>) -> Self {
+        Self {
+            library: Some(library.into()),
+            ..self
+        }
+    }
+
+    pub fn with_path(self, path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: Some(path.into()),
+            ..self
+        }
+    }
+
     pub fn parse(s: impl AsRef<str>) -> Self {
         let input_str = s.as_ref();
         let (metadata, contents) = if let Ok(doc) = YamlFrontMatter::parse(input_str) {
@@ -285,9 +576,33 @@ impl Recipe {
         Self {
             metadata,
             contents: markdown::Parser::default().parse(contents),
+            library: None,
+            path: None,
         }
     }
 
+    /// An estimate of the hands-on time required, summing the durations mentioned in each step.
+    pub fn active_time(&self) -> u64 {
+        self.steps()
+            .iter()
+            .flat_map(|step| step.timers())
+            .map(|timer| timer.seconds())
+            .sum()
+    }
+
+    /// The hottest temperature mentioned in the directions, in degrees Celsius.
+    pub fn max_temperature(&self) -> Option<u32> {
+        self.steps()
+            .iter()
+            .flat_map(|step| step.temperatures())
+            .map(|temperature| temperature.celsius())
+            .max()
+    }
+
+    pub fn steps(&self) -> Vec<Step> {
+        Step::from_recipe(self)
+    }
+
     pub fn sources(&self) -> Vec<Source> {
         self.metadata()
             .map(|md| md.sources().clone())
@@ -311,6 +626,168 @@ impl IntoResponse for Recipe {
     }
 }
 
+/// Edits the YAML frontmatter of a recipe file in place. Only the keys which are set or removed
+/// change: the order of the others, any comments, and the Markdown body are kept byte-for-byte.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FrontmatterEditor {
+    contents: String,
+}
+
+impl FrontmatterEditor {
+    pub fn new(contents: impl Into<String>) -> Self {
+        Self {
+            contents: contents.into(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path).map(Self::new)
+    }
+
+    /// The value of the top-level `key`, if present in readable frontmatter.
+    pub fn get(&self, key: &str) -> Option<serde_yaml::Value> {
+        let mut lines = self.contents.split_inclusive('\n');
+        if lines.next().map(str::trim_end) != Some("---") {
+            return None;
+        }
+
+        let yaml = lines
+            .take_while(|line| !matches!(line.trim_end(), "---" | "..."))
+            .collect::<String>();
+        serde_yaml::from_str::<serde_yaml::Mapping>(&yaml)
+            .ok()?
+            .remove(&serde_yaml::Value::from(key))
+    }
+
+    /// Sets the top-level `key` to `value`, replacing its existing lines, or adding it at the end
+    /// of the frontmatter (which is created if there's none).
+    pub fn set(&mut self, key: &str, value: serde_yaml::Value) -> io::Result<()> {
+        let mut mapping = serde_yaml::Mapping::new();
+        mapping.insert(
+            serde_yaml::Value::String(String::from(Self::key(key)?)),
+            value,
+        );
+        let yaml = serde_yaml::to_string(&mapping)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let yaml = yaml.trim_start_matches("---\n");
+        self.replace(key, Some(yaml.trim_end_matches('\n').to_string() + "\n"));
+        Ok(())
+    }
+
+    /// Removes the top-level `key`, if present.
+    pub fn remove(&mut self, key: &str) -> io::Result<()> {
+        self.replace(Self::key(key)?, None);
+        Ok(())
+    }
+
+    /// Only simple keys can be found by their line, without parsing the YAML.
+    fn key(key: &str) -> io::Result<&str> {
+        if !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            Ok(key)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported frontmatter key {key:?}"),
+            ))
+        }
+    }
+
+    fn replace(&mut self, key: &str, lines: Option<String>) {
+        let input = &self.contents;
+        let mut input_lines = input.split_inclusive('\n');
+
+        if input_lines.next().map(str::trim_end) != Some("---") {
+            // Without frontmatter, there's nothing to remove, but a key can be added
+            if let Some(lines) = lines {
+                self.contents = format!("---\n{lines}---\n{input}");
+            }
+            return;
+        }
+
+        let mut output = String::from(&input[..input.find('\n').map_or(input.len(), |i| i + 1)]);
+        let mut replacement = lines;
+        let mut in_key = false;
+        let mut in_frontmatter = true;
+        for line in input_lines {
+            if in_frontmatter {
+                let trimmed = line.trim_end();
+                if trimmed == "---" || trimmed == "..." {
+                    in_frontmatter = false;
+                    output.extend(replacement.take());
+                } else if line.starts_with([' ', '\t', '-']) {
+                    // Nested or list values continue the preceding key
+                    if in_key {
+                        continue;
+                    }
+                } else {
+                    in_key = trimmed
+                        .strip_prefix(key)
+                        .is_some_and(|rest| rest.starts_with(':'));
+                    if in_key {
+                        output.extend(replacement.take());
+                        continue;
+                    }
+                }
+            }
+            output.push_str(line);
+        }
+
+        self.contents = output;
+    }
+
+    /// Writes the edited recipe to `path` atomically, via a hidden temporary file alongside it, so
+    /// that neither readers nor the filesystem watcher see a partial write. The temporary file is
+    /// flushed to disk before it replaces the recipe, so a crash leaves one version or the other.
+    /// Edits which would leave the frontmatter unreadable are refused.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        if Recipe::parse(&self.contents).metadata().is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the edited frontmatter is invalid",
+            ));
+        }
+
+        let path = path.as_ref();
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(file_name);
+        temp_name.push(".tmp");
+        let temp = path.with_file_name(temp_name);
+
+        let result = fs::File::create(&temp)
+            .and_then(|mut f| {
+                f.write_all(self.contents.as_bytes())?;
+                f.set_permissions(fs::metadata(path)?.permissions())?;
+                f.sync_all()
+            })
+            .and_then(|()| fs::rename(&temp, path));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+            return result;
+        }
+
+        // The rename itself is only durable once the directory is
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 fn is_hidden(entry: &walkdir::DirEntry) -> bool {
     entry
         .file_name()
@@ -319,6 +796,36 @@ fn is_hidden(entry: &walkdir::DirEntry) -> bool {
         .unwrap_or(false)
 }
 
+/// Lists the recipe files beneath `path`, skipping hidden (`.`) and underscore-prefixed entries.
+pub fn walk(path: impl AsRef<Path>) -> impl Iterator<Item = PathBuf> {
+    walkdir::WalkDir::new(path)
+        .follow_links(false)
+        .same_file_system(true)
+        .into_iter()
+        // The root itself is exempt, so that e.g. `~/.pantry` can still be used
+        .filter_entry(|entry| entry.depth() == 0 || !is_hidden(entry))
+        .filter_map(|res| {
+            res.ok()
+                .filter(|entry| !entry.file_type().is_dir())
+                .map(walkdir::DirEntry::into_path)
+        })
+}
+
+/// Applies the same hidden/underscore rules as [`walk`] to an arbitrary path beneath
+/// `root`, such as those reported by the filesystem watcher.
+pub fn is_hidden_path(root: impl AsRef<Path>, path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    path.strip_prefix(root.as_ref())
+        .unwrap_or(path)
+        .components()
+        .any(|c| {
+            c.as_os_str()
+                .to_str()
+                .map(|s| s.starts_with('.') || s.starts_with('_'))
+                .unwrap_or(false)
+        })
+}
+
 fn parse_sectioned_list(
     html: &scraper::Html,
     class: impl AsRef<str>,
@@ -367,3 +874,176 @@ fn parse_unified_list(html: &scraper::Html, id: impl AsRef<str>) -> Option<Vec<S
             .collect::<Vec<_>>()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHILI: &str = "---
+title: Chili
+# Family favourite
+category: Mains
+tags:
+  - spicy
+  - weeknight
+rating: 3
+---
+
+A hearty chili.
+
+rating: not frontmatter
+";
+
+    fn edited(contents: &str, edit: impl FnOnce(&mut FrontmatterEditor)) -> String {
+        let mut editor = FrontmatterEditor::new(contents);
+        edit(&mut editor);
+        editor.contents
+    }
+
+    #[test]
+    fn set_existing_key_preserves_other_lines() {
+        let contents = edited(CHILI, |e| e.set("rating", 5.into()).unwrap());
+        assert_eq!(contents, CHILI.replace("rating: 3\n---", "rating: 5\n---"));
+    }
+
+    #[test]
+    fn set_missing_key_appends_to_frontmatter() {
+        let contents = edited(CHILI, |e| e.set("favorite", true.into()).unwrap());
+        assert_eq!(
+            contents,
+            CHILI.replace("rating: 3\n---", "rating: 3\nfavorite: true\n---")
+        );
+    }
+
+    #[test]
+    fn set_without_frontmatter_creates_it() {
+        let contents = edited("A hearty chili.\n", |e| {
+            e.set("title", "Chili".into()).unwrap()
+        });
+        assert_eq!(contents, "---\ntitle: Chili\n---\nA hearty chili.\n");
+    }
+
+    #[test]
+    fn get_existing_key() {
+        let editor = FrontmatterEditor::new(CHILI);
+        assert_eq!(editor.get("category"), Some("Mains".into()));
+        assert_eq!(
+            editor.get("tags"),
+            Some(serde_yaml::Value::Sequence(vec![
+                "spicy".into(),
+                "weeknight".into()
+            ]))
+        );
+        assert_eq!(editor.get("favorite"), None);
+        assert_eq!(FrontmatterEditor::new("# Chili\n").get("title"), None);
+    }
+
+    #[test]
+    fn remove_existing_key() {
+        let contents = edited(CHILI, |e| e.remove("rating").unwrap());
+        assert_eq!(contents, CHILI.replace("rating: 3\n", ""));
+    }
+
+    #[test]
+    fn remove_missing_key_changes_nothing() {
+        assert_eq!(edited(CHILI, |e| e.remove("favorite").unwrap()), CHILI);
+        let plain = "A hearty chili.\n";
+        assert_eq!(edited(plain, |e| e.remove("favorite").unwrap()), plain);
+    }
+
+    #[test]
+    fn set_replaces_block_sequence() {
+        let tags = serde_yaml::Value::Sequence(vec!["mild".into()]);
+        let contents = edited(CHILI, |e| e.set("tags", tags).unwrap());
+        assert_eq!(
+            contents,
+            CHILI.replace("tags:\n  - spicy\n  - weeknight\n", "tags:\n  - mild\n")
+        );
+    }
+
+    #[test]
+    fn set_replaces_flow_sequence() {
+        let flow = CHILI.replace(
+            "tags:\n  - spicy\n  - weeknight\n",
+            "tags: [spicy, weeknight]\n",
+        );
+        let tags = serde_yaml::Value::Sequence(vec!["mild".into(), "quick".into()]);
+        let contents = edited(&flow, |e| e.set("tags", tags).unwrap());
+        assert_eq!(
+            contents,
+            CHILI.replace(
+                "tags:\n  - spicy\n  - weeknight\n",
+                "tags:\n  - mild\n  - quick\n"
+            )
+        );
+    }
+
+    #[test]
+    fn remove_block_sequence() {
+        let contents = edited(CHILI, |e| e.remove("tags").unwrap());
+        assert_eq!(
+            contents,
+            CHILI.replace("tags:\n  - spicy\n  - weeknight\n", "")
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_keys() {
+        let mut editor = FrontmatterEditor::new(CHILI);
+        let e = editor.set("a: b", true.into()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(editor.contents, CHILI);
+    }
+
+    #[test]
+    fn save_writes_edits() {
+        let dir = std::env::temp_dir().join(format!("pantry-editor-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("chili.md");
+        fs::write(&path, CHILI).unwrap();
+
+        let mut editor = FrontmatterEditor::load(&path).unwrap();
+        editor.set("rating", 4.into()).unwrap();
+        editor.save(&path).unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(saved, CHILI.replace("rating: 3\n---", "rating: 4\n---"));
+    }
+
+    #[test]
+    fn save_refuses_unparseable_frontmatter() {
+        let dir = std::env::temp_dir().join(format!("pantry-invalid-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("chili.md");
+        fs::write(&path, CHILI).unwrap();
+
+        // The title is required
+        let mut editor = FrontmatterEditor::load(&path).unwrap();
+        editor.remove("title").unwrap();
+        let e = editor.save(&path).unwrap_err();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        let leftovers = fs::read_dir(&dir).unwrap().count();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(saved, CHILI);
+        assert_eq!(leftovers, 1);
+    }
+
+    #[test]
+    fn transclude_replaces_whole_placeholders() {
+        let mut recipe = Recipe::parse(
+            "---\ntitle: Dinner\ncategory: Mains\n---\n\n![[Chili]]\n\n![[Chili Verde]]\n\n![[Chili]]\n",
+        );
+        assert_eq!(recipe.transclusions(), ["chili", "chili-verde"]);
+
+        recipe.transclude("chili", "<p>Embedded $1</p>");
+        assert_eq!(recipe.transclusions(), ["chili-verde"]);
+        assert_eq!(recipe.contents().matches("<p>Embedded $1</p>").count(), 2);
+        assert!(!recipe.contents().contains(r#"<a href="/recipe/chili">"#));
+        assert!(recipe
+            .contents()
+            .contains(r#"<a href="/recipe/chili-verde">Chili Verde</a></div>"#));
+    }
+}
@@ -1,23 +1,50 @@
-use crate::templates;
+use crate::{
+    events::{Broadcaster, Change},
+    inventory::Inventory,
+    journal::Journal,
+    library::Libraries,
+    snapshots::Snapshots,
+    tags::Aliases,
+    templates,
+};
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::Infallible,
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::{Arc, RwLock},
+};
 
 use async_compat::CompatExt;
 use axum::{
-    extract::{Path, Query, State},
+    extract::{Form, Path, Query, RawQuery, State},
     http::StatusCode,
-    response::{IntoResponse, Redirect, Response},
-    routing::get,
+    response::{
+        sse::{self, Sse},
+        IntoResponse, Redirect, Response,
+    },
+    routing::{get, post},
     Router,
 };
 use recipe_scraper::{Extract, Scrape};
 use serde::Deserialize;
+use smol::stream::{Stream, StreamExt};
 use thiserror::Error;
-use tracing::info;
+use tracing::{info, warn};
 use url::Url;
 
 #[derive(Debug, Error)]
 enum Error {
+    #[error("history unavailable: {0}")]
+    History(#[from] crate::history::Error),
+    #[error("invalid edit: {0}")]
+    InvalidEdit(String),
     #[error("content not found")]
     NotFound,
+    #[error("i/o error: {0}")]
+    Io(#[from] std::io::Error),
     #[error("failed to fetch url: {0}")]
     Reqwest(#[from] reqwest::Error),
     #[error("xapian error: {0}")]
@@ -27,7 +54,19 @@ enum Error {
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
         match self {
+            Error::History(crate::history::Error::InvalidRevision(_)) => {
+                (StatusCode::BAD_REQUEST, "Invalid revision!")
+            }
+            Error::History(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to read recipe history!",
+            ),
+            Error::InvalidEdit(_) => (StatusCode::BAD_REQUEST, "Invalid metadata edit!"),
             Error::NotFound => (StatusCode::NOT_FOUND, "Content not found!"),
+            Error::Io(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to access recipe directory!",
+            ),
             Error::Reqwest(_) => (StatusCode::NOT_FOUND, "Remote recipe not found!"),
             Error::Xapian(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -43,42 +82,302 @@ type Result<T, E = Error> = std::result::Result<T, E>;
 #[derive(Clone)]
 pub struct AppState {
     xapian: crate::search::AsyncIndex,
+    libraries: Libraries,
+    /// The primary library's directory, which holds the inventory, aisles, meal plans and
+    /// shopping list
+    recipe_dir: PathBuf,
+    inventory: Arc<RwLock<Inventory>>,
+    events: Broadcaster,
+    page_size: u32,
+    /// The snapshot stores of the libraries which aren't tracked by git, by library name
+    snapshots: Arc<BTreeMap<String, Snapshots>>,
+    /// The public URL Pantry is reachable at, if configured
+    base_url: Option<url::Url>,
 }
 
 impl AppState {
-    const DEFAULT_PAGE_SIZE: u32 = 50;
+    pub async fn new(libraries: Libraries, config: crate::config::Search) -> Self {
+        let page_size = config.page_size;
+        let xapian = crate::search::AsyncIndex::new(libraries.clone(), config).unwrap();
+
+        // Libraries tracked by git show its history instead, so need no snapshots
+        let mut snapshots = BTreeMap::new();
+        for library in libraries.iter() {
+            if crate::history::Repository::discover(library.path())
+                .await
+                .is_none()
+            {
+                snapshots.insert(String::from(library.name()), Snapshots::new(library.path()));
+            }
+        }
+
+        Self {
+            xapian,
+            recipe_dir: PathBuf::from(libraries.primary().path()),
+            libraries,
+            page_size,
+            inventory: Default::default(),
+            events: Default::default(),
+            snapshots: Arc::new(snapshots),
+            base_url: None,
+        }
+    }
 
-    pub fn new(path: impl AsRef<std::path::Path>) -> Self {
-        let xapian = crate::search::AsyncIndex::new(&path).unwrap();
-        Self { xapian }
+    pub fn with_base_url(self, base_url: Option<url::Url>) -> Self {
+        Self { base_url, ..self }
+    }
+
+    pub fn inventory(&self) -> Inventory {
+        self.inventory.read().unwrap().clone()
     }
 
     pub async fn query(
         &self,
         query: impl AsRef<str>,
+        filters: &crate::search::Filters,
         start: impl Into<Option<u32>>,
         size: impl Into<Option<u32>>,
     ) -> Result<crate::search::SearchResult, crate::search::Error> {
         self.xapian
             .query(
                 query.as_ref(),
+                filters,
                 start.into().unwrap_or(0),
-                size.into().unwrap_or(Self::DEFAULT_PAGE_SIZE),
+                size.into().unwrap_or(self.page_size),
             )
             .await
     }
 
-    pub async fn recipe(&self, slug: impl AsRef<str>) -> Option<crate::recipe::Recipe> {
-        let results = self
-            .query(format!("slug:{}", slug.as_ref()), 0, 1)
+    pub async fn recipe(
+        &self,
+        library: impl AsRef<str>,
+        slug: impl AsRef<str>,
+    ) -> Option<crate::recipe::Recipe> {
+        let library = self.libraries.get(library)?;
+        let terms = vec![
+            format!("XL:{}", library.name()),
+            format!("Q:{}", slug.as_ref()),
+        ];
+        self.xapian.find(terms, 1).await.ok()?.into_iter().next()
+    }
+
+    /// Finds the recipe identified by `id`, which is either qualified as `library/slug`, or a
+    /// bare slug. Bare slugs are looked up in `preferred` first, then in each library in turn.
+    pub async fn resolve(
+        &self,
+        id: impl AsRef<str>,
+        preferred: Option<&str>,
+    ) -> Option<crate::recipe::Recipe> {
+        let id = id.as_ref();
+        if let Some((library, slug)) = id.split_once('/') {
+            return self.recipe(library, slug).await;
+        }
+
+        let libraries = preferred
+            .into_iter()
+            .chain(self.libraries.iter().map(|library| library.name()))
+            .collect::<Vec<_>>();
+        for library in libraries {
+            if let Some(recipe) = self.recipe(library, id).await {
+                return Some(recipe);
+            }
+        }
+        None
+    }
+
+    /// The git repository tracking `recipe`, if any.
+    pub async fn repository(
+        &self,
+        recipe: &crate::recipe::Recipe,
+    ) -> Option<crate::history::Repository> {
+        let dir = recipe.path()?.parent()?;
+        crate::history::Repository::discover(dir).await
+    }
+
+    /// The cooking journal of `recipe`.
+    pub fn journal(&self, recipe: &crate::recipe::Recipe) -> Option<Journal> {
+        let path = recipe.path()?;
+        Journal::for_recipe(self.libraries.find(path)?.path(), path)
+    }
+
+    /// The recipe whose journal is at `path`, if it is one.
+    fn journal_recipe(&self, path: impl AsRef<std::path::Path>) -> Option<PathBuf> {
+        let path = path.as_ref();
+        Journal::recipe_path(self.libraries.find(path)?.path(), path)
+    }
+
+    /// The snapshot store of the library containing `path`, unless the library is tracked by git,
+    /// whose history is used instead.
+    fn snapshots(&self, path: impl AsRef<std::path::Path>) -> Option<&Snapshots> {
+        self.snapshots.get(self.libraries.find(path)?.name())
+    }
+
+    /// Records the current contents of the recipes at `paths`, so that previous versions can be
+    /// viewed even without git.
+    fn capture_snapshots(&self, paths: impl IntoIterator<Item = impl AsRef<std::path::Path>>) {
+        for path in paths {
+            let path = path.as_ref();
+            if let Some(Err(e)) = self.snapshots(path).map(|s| s.capture(path)) {
+                warn!("Failed to snapshot {path:?}: {e}");
+            }
+        }
+    }
+
+    /// Takes a first snapshot of each recipe which has none, so that its original contents are
+    /// kept once it's edited. Recipes which already have snapshots aren't read.
+    fn capture_initial_snapshots(&self) {
+        for (name, snapshots) in self.snapshots.iter() {
+            let Some(library) = self.libraries.get(name) else {
+                continue;
+            };
+            self.capture_snapshots(
+                crate::recipe::walk(library.path()).filter(|path| !snapshots.has_snapshots(path)),
+            );
+        }
+    }
+
+    fn reload_inventory(&self) {
+        info!("Reloading inventory");
+        match Inventory::load(&self.recipe_dir) {
+            Ok(inventory) => *self.inventory.write().unwrap() = inventory,
+            Err(e) => warn!("Failed to load inventory: {e}"),
+        }
+    }
+
+    /// Recipes which link to the recipe identified by `slug` in `library`. Links are resolved
+    /// from the linking recipe's own library first (see [`AppState::resolve`]), so recipes whose
+    /// links lead to a namesake in another library aren't included.
+    pub async fn backlinks(
+        &self,
+        library: impl AsRef<str>,
+        slug: impl AsRef<str>,
+    ) -> Vec<crate::recipe::Recipe> {
+        let slug = slug.as_ref();
+        let id = format!("{}/{slug}", library.as_ref());
+        let candidates = self
+            .xapian
+            .find(vec![format!("XR:{slug}")], self.page_size)
             .await
-            .ok()?;
+            .unwrap_or_default();
+
+        let mut targets = BTreeMap::new();
+        let mut backlinks = Vec::new();
+        for recipe in candidates {
+            let from = recipe.library().map(String::from);
+            if !targets.contains_key(&from) {
+                let target = self.resolve(slug, from.as_deref()).await;
+                targets.insert(from.clone(), target.and_then(|target| target.id()));
+            }
+            if targets[&from].as_ref() == Some(&id) {
+                backlinks.push(recipe);
+            }
+        }
+        backlinks
+    }
+
+    /// Points each link from `recipe` at the recipe it resolves to, preferring `recipe`'s own
+    /// library, so that following a link leads to the same recipe that backlinks and transclusion
+    /// use. Returns the links whose target does not exist in any library.
+    pub async fn resolve_links(&self, recipe: &mut crate::recipe::Recipe) -> Vec<String> {
+        let mut dangling = Vec::new();
+        for link in recipe.links() {
+            match self
+                .resolve(&link, recipe.library())
+                .await
+                .and_then(|target| target.url())
+            {
+                Some(url) => recipe.relink(&link, url),
+                None => dangling.push(link),
+            }
+        }
+        dangling
+    }
+
+    /// Embeds the recipes referenced via `![[Recipe Name]]` into `recipe`, recursively.
+    pub async fn transclude(&self, recipe: crate::recipe::Recipe) -> crate::recipe::Recipe {
+        let seen = recipe.metadata().map(|md| md.slug()).into_iter().collect();
+        self.expand_transclusions(recipe, seen).await
+    }
+
+    fn expand_transclusions(
+        &self,
+        mut recipe: crate::recipe::Recipe,
+        seen: BTreeSet<String>,
+    ) -> Pin<Box<dyn Future<Output = crate::recipe::Recipe> + Send + '_>> {
+        Box::pin(async move {
+            for slug in recipe.transclusions() {
+                let html = if seen.contains(&slug) {
+                    warn!("Not embedding {slug}, as it would include itself");
+                    format!(
+                        r#"<p class="transclusion cycle"><a href="/recipe/{slug}">{slug}</a> cannot be embedded, as it would include itself.</p>"#
+                    )
+                } else if let Some(embedded) = self.resolve(&slug, recipe.library()).await {
+                    let mut seen = seen.clone();
+                    seen.insert(slug.clone());
+                    let mut embedded = self.expand_transclusions(embedded, seen).await;
+                    self.resolve_links(&mut embedded).await;
+                    embedded.embedded_html()
+                } else {
+                    continue;
+                };
+
+                recipe.transclude(&slug, html);
+            }
+
+            recipe
+        })
+    }
+
+    pub async fn shopping_list(
+        &self,
+        ids: impl IntoIterator<Item = String>,
+    ) -> crate::shopping::ShoppingList {
+        let mut recipes = Vec::new();
+        for id in ids {
+            if let Some(recipe) = self.resolve(id, None).await {
+                recipes.push(recipe);
+            }
+        }
+
+        let aisles = crate::shopping::Aisles::load(&self.recipe_dir).unwrap_or_else(|e| {
+            warn!("Failed to load aisles: {e}");
+            Default::default()
+        });
 
-        let first_result = results.matches().first();
-        first_result.cloned()
+        crate::shopping::ShoppingList::new(&recipes, &aisles)
     }
 
     pub async fn reload(&self, paths: Option<Vec<std::path::PathBuf>>) {
+        let paths = paths.and_then(|paths| {
+            if paths
+                .iter()
+                .any(|p| Inventory::is_inventory_file(&self.recipe_dir, p))
+            {
+                self.reload_inventory();
+            }
+
+            // A change to the tag aliases affects every recipe
+            if paths
+                .iter()
+                .any(|p| Aliases::is_aliases_file(&self.recipe_dir, p))
+            {
+                return None;
+            }
+
+            Some(
+                paths
+                    .into_iter()
+                    // A change to a journal changes how its recipe is indexed
+                    .map(|p| self.journal_recipe(&p).unwrap_or(p))
+                    .filter(|p| self.libraries.find(p).is_some_and(|l| l.contains(p)))
+                    .collect::<Vec<_>>(),
+            )
+        });
+
+        if paths.as_ref().is_some_and(Vec::is_empty) {
+            return;
+        }
+
         if let Some(ref paths) = paths {
             info!(
                 "Reloading entries: {:?}",
@@ -88,13 +387,66 @@ impl AppState {
                     .collect::<Vec<_>>()
                     .join(",")
             );
+            self.capture_snapshots(paths);
         } else {
             info!("Reloading all entries");
+            self.reload_inventory();
+            self.capture_initial_snapshots();
+        }
+        let _ = self.xapian.reindex(paths.clone()).await;
+        self.publish_updates(paths.iter().flatten());
+    }
+
+    /// Notifies open pages of changes to the recipes at `paths`, and to the index as a whole.
+    fn publish_updates<'p>(&self, paths: impl IntoIterator<Item = &'p std::path::PathBuf>) {
+        for id in paths.into_iter().filter_map(|path| {
+            let library = self.libraries.find(path)?;
+            std::fs::File::open(path)
+                .and_then(crate::recipe::Recipe::from_reader)
+                .ok()
+                .and_then(|recipe| recipe.with_library(library.name()).id())
+        }) {
+            self.events.publish(Change::Recipe(id));
+        }
+        self.events.publish(Change::Index);
+    }
+
+    pub async fn reconcile(&self) {
+        match self.xapian.reconcile().await {
+            Ok((updated, removed)) if updated.is_empty() && removed.is_empty() => (),
+            Ok((updated, removed)) => {
+                info!(
+                    "Reconciled index: {} updated, {} removed",
+                    updated.len(),
+                    removed.len()
+                );
+                self.capture_snapshots(&updated);
+                self.publish_updates(&updated);
+            }
+            Err(e) => warn!("Failed to reconcile index: {e}"),
         }
-        let _ = self.xapian.reindex(paths).await;
     }
 
     pub async fn remove(&self, paths: Vec<std::path::PathBuf>) {
+        if paths
+            .iter()
+            .any(|p| Inventory::is_inventory_file(&self.recipe_dir, p))
+        {
+            self.reload_inventory();
+        }
+
+        // Removing a journal (or the tag aliases) doesn't remove any recipes, but does change how
+        // they're indexed
+        let (changed, paths) = paths.into_iter().partition::<Vec<_>, _>(|p| {
+            self.journal_recipe(p).is_some() || Aliases::is_aliases_file(&self.recipe_dir, p)
+        });
+        if !changed.is_empty() {
+            self.reload(Some(changed)).await;
+        }
+        if paths.is_empty() {
+            return;
+        }
+
         info!(
             "Removing entries: {}",
             paths
@@ -104,23 +456,60 @@ impl AppState {
                 .join(",")
         );
         let _ = self.xapian.remove(paths).await;
+        self.events.publish(Change::Index);
     }
 }
 
-#[derive(Deserialize)]
 struct SearchParams {
-    query: String,
+    query: Option<String>,
     start: Option<u32>,
     size: Option<u32>,
+    filters: crate::search::Filters,
+}
+
+impl SearchParams {
+    /// Parses a query string, in which filters (e.g. `tag`) may be repeated.
+    fn parse(params: &str) -> Self {
+        let params = url::form_urlencoded::parse(params.as_bytes()).collect::<Vec<_>>();
+        let get = |key: &str| {
+            params
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.to_string())
+        };
+
+        Self {
+            query: get("query"),
+            start: get("start").and_then(|start| start.parse().ok()),
+            size: get("size").and_then(|size| size.parse().ok()),
+            filters: crate::search::Filters::from_params(params.iter().cloned()),
+        }
+    }
 }
 
 pub fn router(state: AppState) -> Router {
     Router::new()
         .route("/assets/*file", get(asset_handler))
         .route("/", get(index))
+        .route("/events", get(events))
+        .route("/inventory", get(inventory))
+        .route("/meal-plan", get(latest_meal_plan))
+        .route("/meal-plan/:week", get(meal_plan))
         .route("/recipe", get(import_recipe))
-        .route("/recipe/:id", get(recipe))
+        // A single segment is a bare slug (e.g. from a `[Recipe Name]` link), which is resolved
+        // to whichever library holds it
+        .route("/recipe/:library", get(resolve_recipe))
+        .route("/recipe/:library/:id", get(recipe).patch(edit_recipe))
+        .route("/recipe/:library/:id/cook", get(cook))
+        .route("/recipe/:library/:id/diff", get(diff))
+        .route("/recipe/:library/:id/history", get(history))
+        .route("/recipe/:library/:id/journal", post(record_journal))
+        .route("/recipe/:library/:id/snapshot/:hash", get(snapshot))
         .route("/search", get(search))
+        .route(
+            "/shopping-list",
+            get(shopping_list).post(save_shopping_list),
+        )
         .with_state(state)
 }
 
@@ -128,10 +517,45 @@ async fn asset_handler(Path(file): Path<String>) -> Result<crate::assets::Static
     crate::assets::StaticFile::new(file).ok_or(Error::NotFound)
 }
 
+async fn events(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = std::result::Result<sse::Event, Infallible>>> {
+    Sse::new(state.events.subscribe().map(|change| {
+        Ok(sse::Event::default()
+            .event(change.name())
+            .data(change.name()))
+    }))
+}
+
 async fn index() -> impl IntoResponse {
     Redirect::temporary("/search")
 }
 
+async fn inventory(State(state): State<AppState>) -> templates::Inventory<'static> {
+    templates::Inventory::new(state.inventory())
+}
+
+async fn latest_meal_plan(State(state): State<AppState>) -> Result<Response> {
+    let weeks = crate::mealplan::MealPlan::weeks(&state.recipe_dir)?;
+    if let Some(week) = weeks.first() {
+        Ok(Redirect::temporary(&format!("/meal-plan/{week}")).into_response())
+    } else {
+        Ok(templates::MealPlan::new(Default::default(), weeks).into_response())
+    }
+}
+
+async fn meal_plan(
+    Path(week): Path<String>,
+    State(state): State<AppState>,
+) -> Result<templates::MealPlan<'static>> {
+    let weeks = crate::mealplan::MealPlan::weeks(&state.recipe_dir)?;
+    match crate::mealplan::MealPlan::load(&state.recipe_dir, week) {
+        Ok(plan) => Ok(templates::MealPlan::new(plan, weeks)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(Error::NotFound),
+        Err(e) => Err(e.into()),
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ImportRecipeParams {
     url: Url,
@@ -154,25 +578,459 @@ async fn import_recipe(
     }
 }
 
-async fn recipe(
+async fn resolve_recipe(
     Path(slug): Path<String>,
     State(state): State<AppState>,
-) -> Result<templates::Recipe<'static>> {
+) -> Result<Redirect> {
     state
-        .recipe(slug)
+        .resolve(&slug, None)
         .await
-        .map(templates::Recipe::from)
+        .and_then(|recipe| recipe.url())
+        .map(|url| Redirect::temporary(&url))
         .ok_or(Error::NotFound)
 }
 
+async fn recipe(
+    Path((library, slug)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Result<templates::Recipe<'static>> {
+    let recipe = state.recipe(&library, &slug).await.ok_or(Error::NotFound)?;
+    let mut recipe = state.transclude(recipe).await;
+    let backlinks = state.backlinks(&library, &slug).await;
+    let dangling = state.resolve_links(&mut recipe).await;
+    let journal = state
+        .journal(&recipe)
+        .map(|journal| {
+            journal.entries().unwrap_or_else(|e| {
+                warn!("Failed to read journal for {slug}: {e}");
+                Vec::new()
+            })
+        })
+        .unwrap_or_default();
+
+    Ok(templates::Recipe::from(recipe)
+        .with_inventory(&state.inventory())
+        .with_journal(journal)
+        .with_links(backlinks, dangling))
+}
+
+/// Extracts the (possibly repeated) `recipe` parameters from a query string or form body. Each is
+/// a recipe's slug, optionally qualified by its library (as `library/slug`).
+fn recipe_params(params: &str) -> Vec<String> {
+    url::form_urlencoded::parse(params.as_bytes())
+        .filter(|(key, _)| key == "recipe")
+        .map(|(_, id)| id.into_owned())
+        .collect()
+}
+
+async fn shopping_list(
+    RawQuery(query): RawQuery,
+    State(state): State<AppState>,
+) -> templates::ShoppingList<'static> {
+    let ids = recipe_params(query.as_deref().unwrap_or_default());
+    templates::ShoppingList::new(state.shopping_list(ids).await)
+}
+
+async fn save_shopping_list(State(state): State<AppState>, body: String) -> Result<Redirect> {
+    let ids = recipe_params(&body);
+    state
+        .shopping_list(ids.clone())
+        .await
+        .save(&state.recipe_dir, state.base_url.as_ref())?;
+
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(ids.iter().map(|id| ("recipe", id)))
+        .finish();
+    Ok(Redirect::to(&format!("/shopping-list?{query}")))
+}
+
+async fn cook(
+    Path((library, slug)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Result<templates::Cook<'static>> {
+    let mut recipe = state.recipe(&library, &slug).await.ok_or(Error::NotFound)?;
+    state.resolve_links(&mut recipe).await;
+    Ok(templates::Cook::new(&recipe))
+}
+
+#[derive(Deserialize)]
+struct JournalParams {
+    date: time::Date,
+    #[serde(default)]
+    rating: String,
+    #[serde(default)]
+    notes: String,
+    /// One per line
+    #[serde(default)]
+    tweaks: String,
+}
+
+async fn record_journal(
+    Path((library, slug)): Path<(String, String)>,
+    State(state): State<AppState>,
+    Form(params): Form<JournalParams>,
+) -> Result<Redirect> {
+    let recipe = state.recipe(&library, &slug).await.ok_or(Error::NotFound)?;
+    let journal = state.journal(&recipe).ok_or(Error::NotFound)?;
+    let tweaks = params
+        .tweaks
+        .lines()
+        .map(str::trim)
+        .filter(|tweak| !tweak.is_empty())
+        .map(String::from)
+        .collect();
+    let rating = match params.rating.trim() {
+        "" => None,
+        rating => match rating.parse::<u8>().ok().filter(|r| (1..=5).contains(r)) {
+            Some(rating) => Some(rating),
+            None => {
+                return Err(Error::InvalidEdit(format!(
+                    "rating must be from 1 to 5, not {rating:?}"
+                )))
+            }
+        },
+    };
+
+    journal.append(&crate::journal::Entry::new(
+        params.date,
+        rating,
+        params.notes.trim(),
+        tweaks,
+    ))?;
+    Ok(Redirect::to(&recipe.url().unwrap_or_default()))
+}
+
+/// Applies small edits to a recipe's frontmatter, given as a form: `category`, `rating` (empty to
+/// clear it) and `favorite` (`true` or `false`) set those fields, while `add_tag` and `remove_tag`
+/// may be repeated. Open pages are refreshed once the filesystem watcher sees the change, like any
+/// other edit.
+async fn edit_recipe(
+    Path((library, slug)): Path<(String, String)>,
+    State(state): State<AppState>,
+    body: String,
+) -> Result<StatusCode> {
+    use serde_yaml::Value;
+
+    let recipe = state.recipe(&library, &slug).await.ok_or(Error::NotFound)?;
+    let path = recipe.path().ok_or(Error::NotFound)?;
+    let mut editor = crate::recipe::FrontmatterEditor::load(path)?;
+    // Tags are kept in the order they're written, with any new ones added after them
+    let written = editor
+        .get("tags")
+        .and_then(|tags| serde_yaml::from_value::<crate::recipe::Field>(tags).ok())
+        .map(|tags| tags.values().clone())
+        .unwrap_or_default();
+    let mut tags = written.clone();
+    let aliases = Aliases::load(&state.recipe_dir).unwrap_or_else(|e| {
+        warn!("Failed to load tag aliases: {e}");
+        Default::default()
+    });
+
+    for (key, value) in url::form_urlencoded::parse(body.as_bytes()) {
+        let value = value.trim();
+        match key.as_ref() {
+            "category" if value.is_empty() => {
+                return Err(Error::InvalidEdit(String::from("category can't be empty")))
+            }
+            "category" => editor.set("category", Value::String(String::from(value)))?,
+            "rating" if value.is_empty() => editor.remove("rating")?,
+            "rating" => match value.parse::<u8>().ok().filter(|r| (1..=5).contains(r)) {
+                Some(rating) => editor.set(
+                    "rating",
+                    Value::Number(serde_yaml::Number::from(u64::from(rating))),
+                )?,
+                None => {
+                    return Err(Error::InvalidEdit(format!(
+                        "rating must be from 1 to 5, not {value:?}"
+                    )))
+                }
+            },
+            // Rather than recording `favorite: false`, the field is removed
+            "favorite" => match value {
+                "true" => editor.set("favorite", Value::Bool(true))?,
+                "false" => editor.remove("favorite")?,
+                _ => return Err(Error::InvalidEdit(format!("invalid favorite {value:?}"))),
+            },
+            "add_tag" if value.is_empty() => {
+                return Err(Error::InvalidEdit(String::from("tag can't be empty")))
+            }
+            // Adding a tag under another of its aliases would duplicate it
+            "add_tag" => {
+                let tag = aliases.canonicalize(value);
+                if !tags.iter().any(|t| aliases.canonicalize(t) == tag) {
+                    tags.push(tag);
+                }
+            }
+            // The tag may be spelled differently in the frontmatter, or be one of several aliases
+            "remove_tag" => {
+                let tag = aliases.canonicalize(value);
+                tags.retain(|t| aliases.canonicalize(t) != tag);
+            }
+            key => return Err(Error::InvalidEdit(format!("can't edit {key:?}"))),
+        }
+    }
+
+    if tags != written {
+        if tags.is_empty() {
+            editor.remove("tags")?;
+        } else {
+            editor.set(
+                "tags",
+                Value::Sequence(tags.into_iter().map(Value::String).collect()),
+            )?;
+        }
+    }
+
+    editor.save(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::InvalidData => Error::InvalidEdit(e.to_string()),
+        _ => Error::Io(e),
+    })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn history(
+    Path((library, slug)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Result<templates::History<'static>> {
+    let recipe = state.recipe(&library, &slug).await.ok_or(Error::NotFound)?;
+    let commits = match (state.repository(&recipe).await, recipe.path()) {
+        (Some(repo), Some(path)) => Some(repo.log(path).await?),
+        _ => None,
+    };
+    let snapshots = match recipe.path() {
+        Some(path) => state
+            .snapshots(path)
+            .map(|s| s.list(path))
+            .transpose()?
+            .unwrap_or_default(),
+        None => Vec::new(),
+    };
+
+    Ok(templates::History::new(&recipe, commits, snapshots))
+}
+
+async fn snapshot(
+    Path((library, slug, hash)): Path<(String, String, String)>,
+    State(state): State<AppState>,
+) -> Result<templates::Snapshot<'static>> {
+    let recipe = state.recipe(&library, &slug).await.ok_or(Error::NotFound)?;
+    let path = recipe.path().ok_or(Error::NotFound)?;
+    let snapshots = state.snapshots(path).ok_or(Error::NotFound)?;
+    // Only this recipe's own snapshots may be viewed through its URL
+    let snapshot = snapshots
+        .list(path)?
+        .into_iter()
+        .find(|snapshot| snapshot.hash() == hash)
+        .ok_or(Error::NotFound)?;
+    let previous = crate::recipe::Recipe::parse(snapshots.load(&hash)?);
+
+    Ok(templates::Snapshot::new(&recipe, snapshot, previous))
+}
+
+#[derive(Deserialize)]
+struct DiffParams {
+    from: Option<String>,
+    to: String,
+}
+
+async fn diff(
+    Path((library, slug)): Path<(String, String)>,
+    Query(DiffParams { from, to }): Query<DiffParams>,
+    State(state): State<AppState>,
+) -> Result<templates::Diff<'static>> {
+    let recipe = state.recipe(&library, &slug).await.ok_or(Error::NotFound)?;
+    let repo = state.repository(&recipe).await.ok_or(Error::NotFound)?;
+    let path = recipe.path().ok_or(Error::NotFound)?;
+    let lines = repo.diff(path, from.as_deref(), &to).await?;
+
+    Ok(templates::Diff::new(&recipe, from, to, lines))
+}
+
 async fn search(
-    params: Option<Query<SearchParams>>,
+    RawQuery(params): RawQuery,
     State(state): State<AppState>,
 ) -> Result<templates::Search<'static>> {
-    if let Some(Query(SearchParams { query, start, size })) = params {
-        let results = state.query(&query, start, size).await?;
-        Ok(templates::Search::new(query, results))
-    } else {
-        Ok(templates::Search::default())
+    let SearchParams {
+        query,
+        start,
+        size,
+        filters,
+    } = SearchParams::parse(params.as_deref().unwrap_or_default());
+    if query.is_none() && filters.is_empty() {
+        return Ok(templates::Search::default());
+    }
+
+    let query = query.unwrap_or_default();
+    let results = state.query(&query, &filters, start, size).await?;
+    Ok(templates::Search::new(query, results).with_filters(filters))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::Library;
+
+    /// An [`AppState`] serving the given libraries, each a list of `(slug, markdown body)`
+    /// recipes, titled after their slugs.
+    async fn state(name: &str, libraries: &[(&str, &[(&str, &str)])]) -> (AppState, PathBuf) {
+        let root = std::env::temp_dir().join(format!("pantry-{name}-{}", std::process::id()));
+        let mut libs = Vec::new();
+        for (library, recipes) in libraries {
+            let dir = root.join(library);
+            std::fs::create_dir_all(&dir).unwrap();
+            for (slug, body) in recipes.iter() {
+                let md = format!("---\ntitle: {slug}\ncategory: Mains\n---\n\n{body}\n");
+                std::fs::write(dir.join(format!("{slug}.md")), md).unwrap();
+            }
+            libs.push(Library::new(library, dir));
+        }
+
+        let libraries = Libraries::new(libs).unwrap();
+        let state = AppState::new(libraries, Default::default()).await;
+        state.reload(None).await;
+        (state, root)
+    }
+
+    fn titles(recipes: &[crate::recipe::Recipe]) -> Vec<&str> {
+        recipes.iter().filter_map(|recipe| recipe.title()).collect()
+    }
+
+    #[test]
+    fn backlinks_follow_link_resolution_across_libraries() {
+        smol::block_on(async {
+            let (state, root) = state(
+                "backlinks",
+                &[
+                    ("home", &[("chili", ""), ("soup", "Serve with [Chili].")]),
+                    (
+                        "family",
+                        &[
+                            ("chili", ""),
+                            ("tacos", "Top with [Chili]."),
+                            ("stew", "Like [Soup]."),
+                        ],
+                    ),
+                ],
+            )
+            .await;
+
+            let home_chili = state.backlinks("home", "chili").await;
+            let family_chili = state.backlinks("family", "chili").await;
+            let home_soup = state.backlinks("home", "soup").await;
+
+            let mut tacos = state.recipe("family", "tacos").await.unwrap();
+            let tacos_dangling = state.resolve_links(&mut tacos).await;
+            let mut stew = state.recipe("family", "stew").await.unwrap();
+            let stew_dangling = state.resolve_links(&mut stew).await;
+
+            let _ = std::fs::remove_dir_all(&root);
+            assert_eq!(titles(&home_chili), ["soup"]);
+            assert_eq!(titles(&family_chili), ["tacos"]);
+            assert_eq!(titles(&home_soup), ["stew"]);
+
+            // Links lead to the recipes their backlinks are shown on
+            assert!(tacos.contents().contains(r#"href="/recipe/family/chili""#));
+            assert!(stew.contents().contains(r#"href="/recipe/home/soup""#));
+            assert!(tacos_dangling.is_empty() && stew_dangling.is_empty());
+        });
+    }
+
+    #[test]
+    fn unresolved_links_are_dangling() {
+        smol::block_on(async {
+            let (state, root) = state("dangling", &[("home", &[("soup", "With [Bread].")])]).await;
+
+            let mut soup = state.recipe("home", "soup").await.unwrap();
+            let dangling = state.resolve_links(&mut soup).await;
+
+            let _ = std::fs::remove_dir_all(&root);
+            assert_eq!(dangling, ["bread"]);
+            assert!(soup.contents().contains(r#"href="/recipe/bread""#));
+        });
+    }
+
+    #[test]
+    fn transclusion_embeds_recipes_recursively() {
+        smol::block_on(async {
+            let (state, root) = state(
+                "nested",
+                &[(
+                    "home",
+                    &[
+                        ("dinner", "![[Chili]]"),
+                        ("chili", "## Ingredients\n\n- beans\n\n![[Stock]]"),
+                        ("stock", "## Ingredients\n\n- bones"),
+                    ],
+                )],
+            )
+            .await;
+
+            let dinner = state.recipe("home", "dinner").await.unwrap();
+            let dinner = state.transclude(dinner).await;
+
+            let _ = std::fs::remove_dir_all(&root);
+            assert!(dinner.transclusions().is_empty());
+            assert!(dinner.contents().contains("<li>beans</li>"));
+            assert!(dinner.contents().contains("<li>bones</li>"));
+        });
+    }
+
+    #[test]
+    fn transclusion_embeds_shared_recipes_in_each_place() {
+        smol::block_on(async {
+            let (state, root) = state(
+                "diamond",
+                &[(
+                    "home",
+                    &[
+                        ("dinner", "![[Tacos]]\n\n![[Burritos]]"),
+                        ("tacos", "## Ingredients\n\n- shells\n\n![[Salsa]]"),
+                        ("burritos", "## Ingredients\n\n- tortillas\n\n![[Salsa]]"),
+                        ("salsa", "## Ingredients\n\n- tomatoes"),
+                    ],
+                )],
+            )
+            .await;
+
+            let dinner = state.recipe("home", "dinner").await.unwrap();
+            let dinner = state.transclude(dinner).await;
+
+            let _ = std::fs::remove_dir_all(&root);
+            // Embedding the same recipe twice isn't a cycle
+            assert!(!dinner.contents().contains("cycle"));
+            assert_eq!(dinner.contents().matches("<li>tomatoes</li>").count(), 2);
+        });
+    }
+
+    #[test]
+    fn transclusion_cycles_are_broken() {
+        smol::block_on(async {
+            let (state, root) = state(
+                "cycle",
+                &[(
+                    "home",
+                    &[
+                        ("chili", "## Ingredients\n\n- beans\n\n![[Cornbread]]"),
+                        ("cornbread", "## Ingredients\n\n- cornmeal\n\n![[Chili]]"),
+                    ],
+                )],
+            )
+            .await;
+
+            let chili = state.recipe("home", "chili").await.unwrap();
+            let chili = state.transclude(chili).await;
+
+            let _ = std::fs::remove_dir_all(&root);
+            assert!(chili.transclusions().is_empty());
+            assert!(chili.contents().contains("<li>cornmeal</li>"));
+            assert_eq!(chili.contents().matches("<li>beans</li>").count(), 1);
+            assert_eq!(
+                chili
+                    .contents()
+                    .matches(r#"class="transclusion cycle""#)
+                    .count(),
+                1
+            );
+        });
     }
 }
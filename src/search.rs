@@ -1,17 +1,116 @@
 use std::{
+    borrow::Cow,
     collections::BTreeMap,
-    fs, io,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
     path::{Path, PathBuf},
-    sync::{Arc, RwLock},
+    sync::{Arc, OnceLock, RwLock},
     thread,
+    time::SystemTime,
 };
 
+use regex::Regex;
 use smol::channel;
 use thiserror::Error;
+use tracing::warn;
 use xapian::StemStrategy;
 use xapian_rs as xapian;
 
-use crate::recipe::Recipe;
+use crate::{
+    config::FieldKind, journal::Journal, language::Language, library::Libraries, recipe::Recipe,
+    tags::Aliases,
+};
+
+/// Value slots used to store per-document data for faceting and sorting. Numeric values are stored
+/// as zero-padded strings, so that they sort correctly.
+mod slot {
+    pub const CATEGORY: u32 = 1;
+    /// Newline-separated, as (normalized) tags may contain commas, but never newlines
+    pub const TAGS: u32 = 2;
+    pub const ACTIVE_TIME: u32 = 3;
+    pub const MAX_TEMPERATURE: u32 = 4;
+    pub const LIBRARY: u32 = 5;
+    pub const LANGUAGE: u32 = 6;
+    /// The date the recipe was last cooked, as `YYYY-MM-DD`
+    pub const LAST_COOKED: u32 = 7;
+    pub const TIMES_COOKED: u32 = 8;
+    pub const RATING: u32 = 9;
+    /// Set (to `1`) only for favorites
+    pub const FAVORITE: u32 = 10;
+    /// Newline-separated source names (e.g. websites or book titles)
+    pub const SOURCES: u32 = 11;
+    /// Newline-separated domains of the sources' URLs
+    pub const SITES: u32 = 12;
+    /// The first of the slots used by custom fields, which are numbered in order from here
+    pub const FIELDS: u32 = 100;
+}
+
+/// Search fields which custom frontmatter fields may not redefine.
+const BUILTIN_FIELDS: &[&str] = &[
+    "category",
+    "category_is",
+    "desc",
+    "description",
+    "direction",
+    "directions",
+    "favorite",
+    "ingredient",
+    "ingredients",
+    "lang",
+    "library",
+    "links",
+    "name",
+    "rating",
+    "site",
+    "slug",
+    "sort",
+    "source",
+    "source_is",
+    "step",
+    "steps",
+    "tag",
+    "tag_is",
+    "title",
+];
+
+/// A custom frontmatter field which has been configured as searchable.
+#[derive(Clone, Debug)]
+struct CustomField {
+    name: String,
+    prefix: String,
+    kind: FieldKind,
+    slot: Option<u32>,
+}
+
+impl CustomField {
+    /// Validates the configured fields, and assigns each its term prefix and (if it's a facet)
+    /// value slot.
+    fn from_config(fields: &BTreeMap<String, crate::config::Field>) -> Vec<Self> {
+        let mut next_slot = slot::FIELDS;
+        fields
+            .iter()
+            .filter(|(name, _)| {
+                let valid = !name.is_empty()
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    && !BUILTIN_FIELDS.contains(&name.as_str());
+                if !valid {
+                    warn!("Ignoring custom field {name:?}, which can't be used as a search field");
+                }
+                valid
+            })
+            .map(|(name, field)| Self {
+                name: name.clone(),
+                prefix: format!("XF{}:", name.to_uppercase()),
+                kind: field.kind,
+                slot: field.facet.then(|| {
+                    next_slot += 1;
+                    next_slot - 1
+                }),
+            })
+            .collect()
+    }
+}
 
 #[derive(Clone)]
 pub struct AsyncIndex {
@@ -22,26 +121,34 @@ pub struct AsyncIndex {
 }
 
 impl AsyncIndex {
-    pub fn new(recipe_dir: impl AsRef<Path>) -> io::Result<Self> {
+    pub fn new(libraries: Libraries, config: crate::config::Search) -> io::Result<Self> {
         let (tx, requester) = channel::bounded(1);
         let (responder, rx) = channel::bounded(1);
-        let recipe_dir = PathBuf::from(recipe_dir.as_ref());
 
         let thread = Arc::new(
             thread::Builder::new()
                 .name(String::from("xapian-rs"))
-                .spawn(move || Indexer::new(recipe_dir, requester, responder).serve())?,
+                .spawn(move || Indexer::new(libraries, config, requester, responder).serve())?,
         );
 
         Ok(Self { rx, tx, thread })
     }
 
+    pub async fn reconcile(&self) -> Result<(Vec<PathBuf>, Vec<PathBuf>), Error> {
+        self.tx.send(Request::Reconcile).await.unwrap();
+        let response = self.rx.recv().await.unwrap()?;
+        match response {
+            Response::Reconcile { updated, removed } => Ok((updated, removed)),
+            _ => Err(Error::InvalidResponse(Box::new(response))),
+        }
+    }
+
     pub async fn remove(&self, paths: Vec<PathBuf>) -> Result<(), Error> {
         self.tx.send(Request::Remove(paths)).await.unwrap();
         let response = self.rx.recv().await.unwrap()?;
         match response {
-            Response::Reindex => Ok(()),
-            _ => Err(Error::InvalidResponse(response)),
+            Response::Remove => Ok(()),
+            _ => Err(Error::InvalidResponse(Box::new(response))),
         }
     }
 
@@ -54,14 +161,32 @@ impl AsyncIndex {
         let response = self.rx.recv().await.unwrap()?;
         match response {
             Response::Reindex => Ok(()),
-            _ => Err(Error::InvalidResponse(response)),
+            _ => Err(Error::InvalidResponse(Box::new(response))),
+        }
+    }
+
+    /// The recipes indexed with every one of `terms` (e.g. `Q:chili`), which are matched exactly,
+    /// rather than parsed as a query.
+    pub async fn find(&self, terms: Vec<String>, size: u32) -> Result<Vec<Recipe>, Error> {
+        self.tx.send(Request::Find { terms, size }).await.unwrap();
+        let response = self.rx.recv().await.unwrap()?;
+        match response {
+            Response::Find(recipes) => Ok(recipes),
+            _ => Err(Error::InvalidResponse(Box::new(response))),
         }
     }
 
-    pub async fn query(&self, query: &str, start: u32, size: u32) -> Result<SearchResult, Error> {
+    pub async fn query(
+        &self,
+        query: &str,
+        filters: &Filters,
+        start: u32,
+        size: u32,
+    ) -> Result<SearchResult, Error> {
         self.tx
             .send(Request::Search {
                 query: String::from(query),
+                filters: filters.clone(),
                 start,
                 size,
             })
@@ -71,7 +196,50 @@ impl AsyncIndex {
         let response = self.rx.recv().await.unwrap()?;
         match response {
             Response::Search(results) => Ok(results),
-            _ => Err(Error::InvalidResponse(response)),
+            _ => Err(Error::InvalidResponse(Box::new(response))),
+        }
+    }
+}
+
+/// Counts the ratings of the matching recipes, and how many of them are favorites.
+#[derive(Clone, Debug, Default)]
+struct Rater {
+    ratings: Arc<RwLock<BTreeMap<String, usize>>>,
+    favorites: Arc<RwLock<usize>>,
+}
+
+impl Rater {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn facets(&self) -> BTreeMap<String, usize> {
+        self.ratings.read().unwrap().clone()
+    }
+
+    pub fn favorites(&self) -> usize {
+        *self.favorites.read().unwrap()
+    }
+
+    pub fn reset(&self) {
+        self.ratings.write().unwrap().clear();
+        *self.favorites.write().unwrap() = 0;
+    }
+}
+
+impl xapian::MatchSpy for Rater {
+    fn observe(&self, doc: &xapian::Document, _weight: f64) {
+        if let Some(Ok(rating)) = doc.value(slot::RATING) {
+            self.ratings
+                .write()
+                .unwrap()
+                .entry(rating)
+                .and_modify(|count| *count += 1)
+                .or_insert(1);
+        }
+
+        if doc.value::<String>(slot::FAVORITE).is_some() {
+            *self.favorites.write().unwrap() += 1;
         }
     }
 }
@@ -95,7 +263,7 @@ impl Categorizer {
 
 impl xapian::MatchSpy for Categorizer {
     fn observe(&self, doc: &xapian::Document, _weight: f64) {
-        if let Some(Ok(category)) = doc.value(1) {
+        if let Some(Ok(category)) = doc.value(slot::CATEGORY) {
             self.0
                 .write()
                 .unwrap()
@@ -107,9 +275,39 @@ impl xapian::MatchSpy for Categorizer {
 }
 
 #[derive(Clone, Debug, Default)]
-struct Tagger(Arc<RwLock<BTreeMap<String, usize>>>);
+struct Librarian(Arc<RwLock<BTreeMap<String, usize>>>);
 
-impl Tagger {
+impl Librarian {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn facets(&self) -> BTreeMap<String, usize> {
+        self.0.read().unwrap().clone()
+    }
+
+    pub fn reset(&self) {
+        self.0.write().unwrap().clear()
+    }
+}
+
+impl xapian::MatchSpy for Librarian {
+    fn observe(&self, doc: &xapian::Document, _weight: f64) {
+        if let Some(Ok(library)) = doc.value(slot::LIBRARY) {
+            self.0
+                .write()
+                .unwrap()
+                .entry(library)
+                .and_modify(|count| *count += 1)
+                .or_insert(1);
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Linguist(Arc<RwLock<BTreeMap<String, usize>>>);
+
+impl Linguist {
     pub fn new() -> Self {
         Self::default()
     }
@@ -123,11 +321,50 @@ impl Tagger {
     }
 }
 
+impl xapian::MatchSpy for Linguist {
+    fn observe(&self, doc: &xapian::Document, _weight: f64) {
+        if let Some(Ok(language)) = doc.value(slot::LANGUAGE) {
+            self.0
+                .write()
+                .unwrap()
+                .entry(language)
+                .and_modify(|count| *count += 1)
+                .or_insert(1);
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Tagger(Arc<RwLock<BTreeMap<String, usize>>>);
+
+impl Tagger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The tag counts grouped by namespace, those without one being under `""`. Within each
+    /// namespace, tags are keyed by their full (namespaced) name.
+    pub fn namespaces(&self) -> BTreeMap<String, BTreeMap<String, usize>> {
+        let mut namespaces = BTreeMap::<String, BTreeMap<String, usize>>::new();
+        for (tag, count) in self.0.read().unwrap().iter() {
+            let (namespace, _) = crate::tags::split(tag);
+            namespaces
+                .entry(String::from(namespace.unwrap_or_default()))
+                .or_default()
+                .insert(tag.clone(), *count);
+        }
+        namespaces
+    }
+
+    pub fn reset(&self) {
+        self.0.write().unwrap().clear()
+    }
+}
+
 impl xapian::MatchSpy for Tagger {
     fn observe(&self, doc: &xapian::Document, _weight: f64) {
-        if let Some(Ok(value)) = doc.value::<String>(2) {
-            let tags = value.split(',').collect::<Vec<_>>();
-            for tag in tags {
+        if let Some(Ok(value)) = doc.value::<String>(slot::TAGS) {
+            for tag in value.lines() {
                 self.0
                     .write()
                     .unwrap()
@@ -139,6 +376,44 @@ impl xapian::MatchSpy for Tagger {
     }
 }
 
+/// Counts the values stored newline-separated in a slot, such as a custom field's, or the sources.
+#[derive(Clone, Debug)]
+struct FieldCounter {
+    slot: u32,
+    counts: Arc<RwLock<BTreeMap<String, usize>>>,
+}
+
+impl FieldCounter {
+    pub fn new(slot: u32) -> Self {
+        Self {
+            slot,
+            counts: Default::default(),
+        }
+    }
+
+    pub fn facets(&self) -> BTreeMap<String, usize> {
+        self.counts.read().unwrap().clone()
+    }
+
+    pub fn reset(&self) {
+        self.counts.write().unwrap().clear()
+    }
+}
+
+impl xapian::MatchSpy for FieldCounter {
+    fn observe(&self, doc: &xapian::Document, _weight: f64) {
+        if let Some(Ok(value)) = doc.value::<String>(self.slot) {
+            let mut counts = self.counts.write().unwrap();
+            for value in value.lines() {
+                counts
+                    .entry(String::from(value))
+                    .and_modify(|count| *count += 1)
+                    .or_insert(1);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("xapian is shutting down: {0}")]
@@ -146,7 +421,7 @@ pub enum Error {
     #[error("xapian is shutting down: {0}")]
     ChannelTx(#[from] channel::SendError<Request>),
     #[error("invalid response: {0:?}")]
-    InvalidResponse(Response),
+    InvalidResponse(Box<Response>),
     #[error("i/o error: {0}")]
     Io(#[from] io::Error),
 }
@@ -154,7 +429,11 @@ pub enum Error {
 pub struct Indexer {
     db: xapian::WritableDatabase,
     term_generator: xapian::TermGenerator,
-    recipe_dir: PathBuf,
+    aliases: Aliases,
+    fields: Vec<CustomField>,
+    indexed: BTreeMap<PathBuf, Fingerprint>,
+    language: Language,
+    libraries: Libraries,
     searcher: Searcher,
     requests: channel::Receiver<Request>,
     responses: channel::Sender<Result<Response, Error>>,
@@ -162,35 +441,70 @@ pub struct Indexer {
 
 impl Indexer {
     pub fn new(
-        recipe_dir: impl AsRef<Path>,
+        libraries: Libraries,
+        config: crate::config::Search,
         requests: channel::Receiver<Request>,
         responses: channel::Sender<Result<Response, Error>>,
     ) -> Self {
         let db = xapian::WritableDatabase::inmemory();
-        let recipe_dir = PathBuf::from(recipe_dir.as_ref());
         let mut term_generator = xapian::TermGenerator::default();
-        let stemmer = xapian::Stem::for_language("en");
-        let searcher = Searcher::new(db.read_only(), &stemmer);
+        let language = Language::parse(&config.language).unwrap_or_else(|| {
+            warn!("Unsupported language {:?}, using English", config.language);
+            Language::default()
+        });
+        let fields = CustomField::from_config(&config.fields);
+        let aliases = load_aliases(&libraries);
+        let searcher = Searcher::new(
+            db.read_only(),
+            language,
+            config.synonyms,
+            &fields,
+            aliases.clone(),
+        );
 
         term_generator.set_database(&db);
-        term_generator.set_stemmer(&stemmer);
         term_generator.set_stemming_strategy(xapian::StemStrategy::All);
 
         Self {
             db,
             term_generator,
+            aliases,
+            fields,
+            indexed: Default::default(),
+            language,
+            libraries,
             requests,
-            recipe_dir,
             searcher,
             responses,
         }
     }
 
-    pub fn index_recipe(&mut self, id: impl AsRef<Path>, recipe: &Recipe) {
+    /// Indexes `recipe`, along with the entries of its cooking journal.
+    pub fn index_recipe(
+        &mut self,
+        id: impl AsRef<Path>,
+        recipe: &Recipe,
+        journal: &[crate::journal::Entry],
+    ) {
         let mut doc = xapian::Document::default();
         self.term_generator.set_document(&doc);
         doc.set_data(serde_json::to_string(recipe).unwrap());
 
+        // Each recipe is stemmed according to its own language
+        let language = match recipe.metadata().and_then(|md| md.lang()) {
+            Some(lang) => Language::parse(lang).unwrap_or_else(|| {
+                warn!(
+                    "Unsupported language {lang:?}, using {}",
+                    self.language.name()
+                );
+                self.language
+            }),
+            None => self.language,
+        };
+        self.term_generator.set_stemmer(language.stemmer());
+        doc.add_boolean_term(format!("XLANG:{}", language.code()));
+        doc.set_value(slot::LANGUAGE, language.code());
+
         let id = id.as_ref().to_string_lossy();
         let idterm = format!("I:{id}");
         doc.add_boolean_term(&idterm);
@@ -200,6 +514,15 @@ impl Indexer {
             doc.add_boolean_term(&slugterm)
         }
 
+        if let Some(library) = recipe.library() {
+            doc.add_boolean_term(format!("XL:{library}"));
+            doc.set_value(slot::LIBRARY, library);
+        }
+
+        for link in recipe.links() {
+            doc.add_boolean_term(format!("XR:{link}"));
+        }
+
         if let Some(title) = recipe.metadata().map(|md| md.title()) {
             self.term_generator.index_text(title, None, "");
             self.term_generator.index_text(title, None, "S:");
@@ -209,25 +532,52 @@ impl Indexer {
         if let Some(category) = recipe.metadata().map(|md| md.category()) {
             self.term_generator.index_text(category, None, "XC:");
             self.term_generator.increase_termpos(None);
-            doc.set_value(1, category.as_ref());
+            doc.add_boolean_term(format!("XCAT:{}", normalize_filter(category)));
+            doc.set_value(slot::CATEGORY, category.as_ref());
+        }
+
+        if let Some(rating) = recipe.metadata().and_then(|md| md.rating()) {
+            doc.add_boolean_term(format!("XRATING:{rating}"));
+            doc.set_value(slot::RATING, rating.to_string());
+        }
+
+        if recipe.metadata().is_some_and(|md| md.is_favorite()) {
+            doc.add_boolean_term("XFAV:true");
+            doc.set_value(slot::FAVORITE, "1");
         }
 
         if let Some(sources) = recipe.metadata().map(|md| md.sources()) {
+            let mut names = std::collections::BTreeSet::new();
+            let mut sites = std::collections::BTreeSet::new();
             for src in sources {
                 self.term_generator.index_text(src.name(), None, "XS:");
                 self.term_generator.increase_termpos(None);
+                doc.add_boolean_term(format!("XSRC:{}", normalize_filter(src.name())));
+                names.insert(src.name().split_whitespace().collect::<Vec<_>>().join(" "));
 
-                if let Some(domain) = src.url().and_then(|url| url.domain().map(String::from)) {
-                    self.term_generator.index_text(domain, None, "XD:");
-                    self.term_generator.increase_termpos(None);
+                // Books have URLs too, but only to search for them
+                if let crate::recipe::Source::Url { url, .. } = src {
+                    if let Some(site) = site(url) {
+                        doc.add_boolean_term(format!("XD:{site}"));
+                        sites.insert(site);
+                    }
                 }
             }
+            names.remove("");
+            doc.set_value(slot::SOURCES, Vec::from_iter(names).join("\n"));
+            doc.set_value(slot::SITES, Vec::from_iter(sites).join("\n"));
         }
 
         if let Some(tags) = recipe.metadata().map(|md| md.tags()) {
-            let tag_value = Vec::from_iter(tags.clone()).join(",");
-            doc.set_value(2, tag_value);
+            let tags = tags
+                .iter()
+                .map(|tag| self.aliases.canonicalize(tag))
+                .filter(|tag| !tag.is_empty())
+                .collect::<std::collections::BTreeSet<_>>();
+            let tag_value = Vec::from_iter(tags.clone()).join("\n");
+            doc.set_value(slot::TAGS, tag_value);
             for tag in tags {
+                doc.add_boolean_term(format!("XTAG:{tag}"));
                 self.term_generator.index_text(tag, None, "XT:");
                 self.term_generator.increase_termpos(None);
             }
@@ -247,55 +597,213 @@ impl Indexer {
             self.term_generator.increase_termpos(None);
         }
 
+        for field in &self.fields {
+            let Some(values) = recipe.metadata().and_then(|md| md.field(&field.name)) else {
+                continue;
+            };
+
+            // Values are compared case-insensitively, and must each fit on a line of their slot
+            let values = values
+                .values()
+                .iter()
+                .map(normalize_filter)
+                .filter(|value| !value.is_empty())
+                .collect::<Vec<_>>();
+
+            for value in &values {
+                match field.kind {
+                    FieldKind::Text => {
+                        self.term_generator
+                            .index_text(value, None, field.prefix.as_str());
+                        self.term_generator.increase_termpos(None);
+                    }
+                    FieldKind::Filter => doc.add_boolean_term(format!("{}{value}", field.prefix)),
+                }
+            }
+
+            if let Some(slot) = field.slot {
+                doc.set_value(slot, values.join("\n"));
+            }
+        }
+
+        doc.set_value(slot::ACTIVE_TIME, format!("{:010}", recipe.active_time()));
+        if let Some(temperature) = recipe.max_temperature() {
+            doc.set_value(slot::MAX_TEMPERATURE, format!("{temperature:05}"));
+        }
+
+        doc.set_value(slot::TIMES_COOKED, format!("{:010}", journal.len()));
+        if let Some(date) = journal.iter().map(|entry| entry.date()).max() {
+            doc.set_value(slot::LAST_COOKED, date.to_string());
+        }
+
         self.db.replace_document_by_term(&idterm, doc);
     }
 
+    /// Indexes the recipe at `path`, unless its contents are unchanged since it was last indexed
+    /// (or `force` is set). Returns whether the recipe was (re)indexed.
+    fn index_file(&mut self, path: impl AsRef<Path>, force: bool) -> bool {
+        let path = path.as_ref();
+        let Ok(contents) = fs::read_to_string(path) else {
+            return false;
+        };
+
+        let library = self.libraries.find(path);
+        let journal = library
+            .and_then(|library| Journal::for_recipe(library.path(), path))
+            .map(|journal| {
+                journal.entries().unwrap_or_else(|e| {
+                    warn!("Failed to read the journal of {path:?}: {e}");
+                    Vec::new()
+                })
+            })
+            .unwrap_or_default();
+
+        let fingerprint = Fingerprint::new(path, &contents, &journal);
+        let unchanged = self
+            .indexed
+            .get(path)
+            .is_some_and(|fp| fp.hash == fingerprint.hash);
+
+        if !unchanged || force {
+            let mut recipe = Recipe::parse(contents).with_path(path);
+            if let Some(library) = library {
+                recipe = recipe.with_library(library.name());
+            }
+            self.index_recipe(path, &recipe, &journal);
+        }
+        self.indexed.insert(PathBuf::from(path), fingerprint);
+
+        !unchanged || force
+    }
+
+    /// Removes the recipe at `path`, or every recipe beneath it if it was a directory.
     fn remove_recipe(&mut self, path: impl AsRef<Path>) {
-        let id = path.as_ref().to_string_lossy();
-        let idterm = format!("I:{id}");
-        self.db.delete_document_by_term(idterm)
+        let path = path.as_ref();
+        let mut paths = self
+            .indexed
+            .keys()
+            .filter(|p| p.starts_with(path))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !paths.iter().any(|p| p == path) {
+            paths.push(PathBuf::from(path));
+        }
+
+        for path in paths {
+            let id = path.to_string_lossy();
+            let idterm = format!("I:{id}");
+            self.db.delete_document_by_term(idterm);
+            self.indexed.remove(&path);
+        }
+    }
+
+    /// Lists the recipe files in every library.
+    fn walk(&self) -> Vec<PathBuf> {
+        self.libraries
+            .iter()
+            .flat_map(|library| crate::recipe::walk(library.path()))
+            .collect()
+    }
+
+    /// Brings the index in line with the recipe libraries, in case any filesystem events were
+    /// missed: new or modified recipes are (re)indexed, and vanished ones are removed.
+    fn reconcile(&mut self) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let present = self
+            .walk()
+            .into_iter()
+            .map(|path| {
+                let modified = modified(&path);
+                (path, modified)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let removed = self
+            .indexed
+            .keys()
+            .filter(|path| !present.contains_key(*path))
+            .cloned()
+            .collect::<Vec<_>>();
+        // Only files whose modification time changed need to be read and hashed
+        let mut updated = present
+            .into_iter()
+            .filter(|(path, modified)| {
+                self.indexed
+                    .get(path)
+                    .is_none_or(|fp| fp.modified != *modified)
+            })
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+
+        for path in &removed {
+            self.remove_recipe(path);
+        }
+        updated.retain(|path| self.index_file(path, false));
+
+        (updated, removed)
     }
 
     fn handle_request(&mut self, req: &Request) -> Result<Response, Error> {
         use Request::*;
-        let recipe_dir = self.recipe_dir.clone();
         match req {
             &ReindexAll => {
-                for (path, recipe) in Recipe::load_all(&recipe_dir) {
-                    self.index_recipe(path, &recipe);
+                // Changes to the tag aliases affect every recipe
+                self.aliases = load_aliases(&self.libraries);
+                self.searcher.aliases = self.aliases.clone();
+                for path in self.walk() {
+                    self.index_file(path, true);
                 }
                 Ok(Response::Reindex)
             }
             ReindexSome(paths) => {
-                for (path, recipe) in paths.iter().filter_map(|p| {
-                    fs::File::open(p)
-                        .and_then(Recipe::from_reader)
-                        .map(|r| (p, r))
-                        .ok()
-                }) {
-                    self.index_recipe(path, &recipe);
+                for path in paths {
+                    self.index_file(path, false);
                 }
 
                 Ok(Response::Reindex)
             }
+            &Reconcile => {
+                let (updated, removed) = self.reconcile();
+                Ok(Response::Reconcile { updated, removed })
+            }
             Remove(paths) => {
                 for path in paths {
                     self.remove_recipe(path);
                 }
                 Ok(Response::Remove)
             }
-            Search { query, size, start } => {
-                let mset = self.searcher.search(query, *start, *size);
-                let results = mset.matches().map(|m| {
-                    let doc = m.document();
-                    serde_json::from_slice(&doc.data()).unwrap()
-                });
-
-                Ok(Response::Search(SearchResult::new(
-                    self.searcher.categories(),
-                    results,
-                    self.searcher.tags(),
-                )))
+            Find { terms, size } => {
+                let recipes = self
+                    .searcher
+                    .find(terms, *size)
+                    .into_iter()
+                    .map(|doc| serde_json::from_slice(&doc.data()).unwrap())
+                    .collect();
+                Ok(Response::Find(recipes))
+            }
+            Search {
+                query,
+                filters,
+                size,
+                start,
+            } => {
+                let results = self
+                    .searcher
+                    .search(query, filters, *start, *size)
+                    .into_iter()
+                    .map(|doc| serde_json::from_slice(&doc.data()).unwrap());
+
+                Ok(Response::Search(
+                    SearchResult::new(
+                        self.searcher.categories(),
+                        self.searcher.languages(),
+                        self.searcher.libraries(),
+                        results,
+                        self.searcher.tags(),
+                    )
+                    .with_fields(self.searcher.fields())
+                    .with_ratings(self.searcher.ratings(), self.searcher.favorites())
+                    .with_sources(self.searcher.sources(), self.searcher.sites()),
+                ))
             }
         }
     }
@@ -312,11 +820,17 @@ impl Indexer {
 
 #[derive(Clone, Debug)]
 pub enum Request {
+    Find {
+        terms: Vec<String>,
+        size: u32,
+    },
+    Reconcile,
     ReindexAll,
     ReindexSome(Vec<PathBuf>),
     Remove(Vec<PathBuf>),
     Search {
         query: String,
+        filters: Filters,
         size: u32,
         start: u32,
     },
@@ -324,53 +838,229 @@ pub enum Request {
 
 #[derive(Clone, Debug)]
 pub enum Response {
+    Find(Vec<Recipe>),
+    Reconcile {
+        updated: Vec<PathBuf>,
+        removed: Vec<PathBuf>,
+    },
     Reindex,
     Remove,
     Search(SearchResult),
 }
 
+/// Normalizes the value of an exact filter (e.g. a category), which is matched regardless of case
+/// and spacing.
+fn normalize_filter(value: impl AsRef<str>) -> String {
+    value
+        .as_ref()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The site a source URL belongs to, e.g. `seriouseats.com`.
+fn site(url: &url::Url) -> Option<String> {
+    url.domain()
+        .map(|domain| domain.trim_start_matches("www.").to_lowercase())
+}
+
+/// Loads the tag aliases from the primary library.
+fn load_aliases(libraries: &Libraries) -> Aliases {
+    Aliases::load(libraries.primary().path()).unwrap_or_else(|e| {
+        warn!("Failed to load tag aliases: {e}");
+        Aliases::default()
+    })
+}
+
+fn modified(path: impl AsRef<Path>) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|md| md.modified()).ok()
+}
+
+/// Identifies the version of a recipe file (and its journal) which was indexed, so that unchanged
+/// files (e.g. those reported by a polling watcher, or touched without modification) aren't
+/// needlessly reindexed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Fingerprint {
+    modified: Option<SystemTime>,
+    hash: u64,
+}
+
+impl Fingerprint {
+    fn new(
+        path: impl AsRef<Path>,
+        contents: impl AsRef<str>,
+        journal: &[crate::journal::Entry],
+    ) -> Self {
+        let mut hasher = DefaultHasher::new();
+        contents.as_ref().hash(&mut hasher);
+        journal.hash(&mut hasher);
+
+        Self {
+            modified: modified(path),
+            hash: hasher.finish(),
+        }
+    }
+}
+
 pub struct Searcher {
     db: xapian::Database,
     enquire: xapian::Enquire,
     query_parser: xapian::QueryParser,
     categorizer: Categorizer,
+    language: Language,
+    librarian: Librarian,
+    linguist: Linguist,
+    rater: Rater,
+    sites: FieldCounter,
+    sources: FieldCounter,
     tagger: Tagger,
+    field_counters: Vec<(String, FieldCounter)>,
+    fields: Vec<CustomField>,
+    /// The names of the fields (built-in and custom) which are searched as free text
+    text_fields: Vec<String>,
+    synonyms: BTreeMap<String, Vec<String>>,
+    aliases: Aliases,
+}
+
+fn language_filter_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(^|[\s(])lang:([\w-]+)").unwrap())
+}
+
+fn rating_range_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(^|[\s(])rating:([1-5]?)\.\.([1-5]?)").unwrap())
+}
+
+fn filter_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(^|[\s(])(\w+):("[^"]*"|[^\s()"]+)"#).unwrap())
+}
+
+/// The built-in free-text fields, and their term prefixes.
+const TEXT_FIELDS: &[(&str, &str)] = &[
+    ("desc", "D:"),
+    ("description", "D:"),
+    ("ingredient", "XI:"),
+    ("ingredients", "XI:"),
+    ("step", "XP:"),
+    ("steps", "XP:"),
+    ("direction", "XP:"),
+    ("directions", "XP:"),
+    ("name", "S:"),
+    ("title", "S:"),
+    ("source", "XS:"),
+    ("category", "XC:"),
+    ("tag", "XT:"),
+];
+
+fn synonym_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(^|[\s(])([+-]?)(?:(\w+):)?([^\s()":*]+)"#).unwrap())
+}
+
+fn sort_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(^|\s)sort:(\w+)").unwrap())
 }
 
 impl Searcher {
-    pub fn new(db: xapian::Database, stemmer: &xapian::Stem) -> Self {
+    fn new(
+        db: xapian::Database,
+        language: Language,
+        synonyms: BTreeMap<String, Vec<String>>,
+        fields: &[CustomField],
+        aliases: Aliases,
+    ) -> Self {
         let categorizer = Categorizer::new();
+        let librarian = Librarian::new();
+        let linguist = Linguist::new();
+        let rater = Rater::new();
+        let sites = FieldCounter::new(slot::SITES);
+        let sources = FieldCounter::new(slot::SOURCES);
         let tagger = Tagger::new();
         let mut enquire = xapian::Enquire::new(&db);
         enquire.add_matchspy(&categorizer);
+        enquire.add_matchspy(&librarian);
+        enquire.add_matchspy(&linguist);
+        enquire.add_matchspy(&rater);
+        enquire.add_matchspy(&sites);
+        enquire.add_matchspy(&sources);
         enquire.add_matchspy(&tagger);
 
+        let field_counters = fields
+            .iter()
+            .filter_map(|field| Some((field.name.clone(), FieldCounter::new(field.slot?))))
+            .collect::<Vec<_>>();
+        for (_, counter) in &field_counters {
+            enquire.add_matchspy(counter);
+        }
+
+        let text_fields = TEXT_FIELDS
+            .iter()
+            .map(|(field, _)| String::from(*field))
+            .chain(
+                fields
+                    .iter()
+                    .filter(|field| field.kind == FieldKind::Text)
+                    .map(|field| field.name.clone()),
+            )
+            .collect();
+
         let mut query_parser = xapian::QueryParser::default();
-        query_parser.set_stemmer(stemmer);
+        query_parser.set_stemmer(language.stemmer());
         query_parser.set_stemming_strategy(StemStrategy::All);
 
-        query_parser.add_prefix("desc", "D:");
-        query_parser.add_prefix("description", "D:");
-        query_parser.add_prefix("ingredient", "XI:");
-        query_parser.add_prefix("ingredients", "XI:");
-        query_parser.add_prefix("step", "XP:");
-        query_parser.add_prefix("steps", "XP:");
-        query_parser.add_prefix("direction", "XP:");
-        query_parser.add_prefix("directions", "XP:");
-        query_parser.add_prefix("name", "S:");
-        query_parser.add_prefix("title", "S:");
-        query_parser.add_prefix("source", "XS:");
-        query_parser.add_prefix("category", "XC:");
-        query_parser.add_prefix("tag", "XT:");
+        for (field, prefix) in TEXT_FIELDS {
+            query_parser.add_prefix(*field, *prefix);
+        }
+        query_parser.add_boolean_prefix::<_, &str>("favorite", "XFAV:", None);
+        query_parser.add_boolean_prefix::<_, &str>("rating", "XRATING:", None);
         query_parser.add_boolean_prefix::<_, &str>("slug", "Q:", None);
+        query_parser.add_boolean_prefix::<_, &str>("lang", "XLANG:", None);
+        query_parser.add_boolean_prefix::<_, &str>("library", "XL:", None);
+        query_parser.add_boolean_prefix::<_, &str>("links", "XR:", None);
+        // Exact filters, alongside the free-text fields above. Several filters on the same field
+        // match any of their values, except for tags, where each must match
+        query_parser.add_boolean_prefix::<_, &str>("category_is", "XCAT:", None);
         query_parser.add_boolean_prefix::<_, &str>("site", "XD:", None);
+        query_parser.add_boolean_prefix::<_, &str>("source_is", "XSRC:", None);
+        query_parser.add_boolean_prefix::<_, &str>("tag_is", "XTAG:", Some(""));
+
+        for field in fields {
+            match field.kind {
+                FieldKind::Text => {
+                    query_parser.add_prefix(field.name.as_str(), field.prefix.as_str())
+                }
+                FieldKind::Filter => query_parser.add_boolean_prefix::<_, &str>(
+                    field.name.as_str(),
+                    field.prefix.as_str(),
+                    None,
+                ),
+            }
+        }
 
         Searcher {
             db,
             categorizer,
+            language,
+            librarian,
+            linguist,
+            rater,
+            sites,
+            sources,
             tagger,
+            field_counters,
+            fields: fields.to_vec(),
+            text_fields,
             enquire,
             query_parser,
+            synonyms: synonyms
+                .into_iter()
+                .map(|(word, synonyms)| (word.to_lowercase(), synonyms))
+                .collect(),
+            aliases,
         }
     }
 
@@ -378,35 +1068,370 @@ impl Searcher {
         self.categorizer.facets().into_iter()
     }
 
-    pub fn tags(&self) -> impl Iterator<Item = (String, usize)> {
-        self.tagger.facets().into_iter()
+    /// The value counts of each custom field which is a facet.
+    pub fn fields(&self) -> impl Iterator<Item = (String, BTreeMap<String, usize>)> + '_ {
+        self.field_counters
+            .iter()
+            .map(|(name, counter)| (name.clone(), counter.facets()))
+    }
+
+    pub fn languages(&self) -> impl Iterator<Item = (String, usize)> {
+        self.linguist.facets().into_iter()
+    }
+
+    pub fn libraries(&self) -> impl Iterator<Item = (String, usize)> {
+        self.librarian.facets().into_iter()
+    }
+
+    /// Normalizes any `lang:` filters in `query` to language codes (e.g. `lang:german` becomes
+    /// `lang:de`), returning the first such language, whose stemmer the query should use.
+    fn normalize_languages(query: &str) -> (String, Option<Language>) {
+        let mut first = None;
+        let query = language_filter_regex().replace_all(query, |caps: &regex::Captures| {
+            match Language::parse(&caps[2]) {
+                Some(language) => {
+                    first.get_or_insert(language);
+                    format!("{}lang:{}", &caps[1], language.code())
+                }
+                None => String::from(&caps[0]),
+            }
+        });
+
+        (query.into_owned(), first)
+    }
+
+    /// The number of favorites among the last search's results.
+    pub fn favorites(&self) -> usize {
+        self.rater.favorites()
     }
 
-    fn search(&mut self, query: impl AsRef<str>, start: u32, size: u32) -> xapian::MSet {
+    pub fn ratings(&self) -> impl Iterator<Item = (String, usize)> {
+        self.rater.facets().into_iter()
+    }
+
+    /// Rewrites any rating ranges in `query` (e.g. `rating:4..`, `rating:..2` or `rating:2..3`)
+    /// into an `OR` of the ratings they include.
+    fn expand_rating_ranges(query: &str) -> String {
+        rating_range_regex()
+            .replace_all(query, |caps: &regex::Captures| {
+                let from = caps[2].parse().unwrap_or(1);
+                let to = caps[3].parse().unwrap_or(5);
+                let ratings = (from..=to)
+                    .map(|rating: u8| format!("rating:{rating}"))
+                    .collect::<Vec<_>>();
+                if ratings.is_empty() {
+                    // An empty range matches nothing, as no recipe is rated 0
+                    format!("{}rating:0", &caps[1])
+                } else {
+                    format!("{}({})", &caps[1], ratings.join(" OR "))
+                }
+            })
+            .into_owned()
+    }
+
+    /// Whether `name` is a custom field which is matched exactly.
+    fn is_filter_field(&self, name: &str) -> bool {
+        self.fields
+            .iter()
+            .any(|field| field.name == name && field.kind == FieldKind::Filter)
+    }
+
+    /// A query matching every one of `filters`, if there are any. Several values for the same
+    /// filter match any of them, except for tags, where each must match.
+    fn filter_query(&mut self, filters: &Filters) -> Option<xapian::Query> {
+        let term = |term: String| xapian::Query::term(term, None, None);
+        let mut groups = BTreeMap::<&Filter, Vec<xapian::Query>>::new();
+        for (filter, value) in filters.iter() {
+            let query = match filter {
+                Filter::Category => term(format!("XCAT:{}", normalize_filter(value))),
+                Filter::Favorite => term(format!("XFAV:{}", value.to_lowercase())),
+                Filter::Language => term(format!(
+                    "XLANG:{}",
+                    Language::parse(value).map_or(value, |language| language.code())
+                )),
+                Filter::Library => term(format!("XL:{value}")),
+                // Ratings of at least the value
+                Filter::Rating => match value.parse::<u8>() {
+                    Ok(from) if (1..=5).contains(&from) => (from..=5)
+                        .map(|rating| term(format!("XRATING:{rating}")))
+                        .reduce(|a, b| xapian::Query::combine(xapian::Operator::Or, a, b))
+                        .unwrap(),
+                    _ => xapian::Query::match_nothing(),
+                },
+                Filter::Site => term(format!(
+                    "XD:{}",
+                    normalize_filter(value).trim_start_matches("www.")
+                )),
+                Filter::Source => term(format!("XSRC:{}", normalize_filter(value))),
+                Filter::Tag => term(format!("XTAG:{}", self.aliases.canonicalize(value))),
+                Filter::Field(name) => match self.fields.iter().find(|f| &f.name == name) {
+                    Some(field) if field.kind == FieldKind::Filter => {
+                        term(format!("{}{}", field.prefix, normalize_filter(value)))
+                    }
+                    // Free-text fields have no exact terms, so match the value as a phrase
+                    Some(field) => self.query_parser.parse_query(
+                        format!(r#""{value}""#),
+                        None,
+                        field.prefix.as_str(),
+                    ),
+                    None => xapian::Query::match_nothing(),
+                },
+            };
+            groups.entry(filter).or_default().push(query);
+        }
+
+        groups
+            .into_iter()
+            .map(|(filter, queries)| {
+                queries
+                    .into_iter()
+                    .reduce(|a, b| {
+                        let op = match filter {
+                            Filter::Tag => xapian::Operator::And,
+                            _ => xapian::Operator::Or,
+                        };
+                        xapian::Query::combine(op, a, b)
+                    })
+                    .unwrap()
+            })
+            .reduce(|a, b| xapian::Query::combine(xapian::Operator::And, a, b))
+    }
+
+    /// Removes any `sort:` directive from `query` (e.g. `sort:last_cooked`), returning the value
+    /// slot results should be ordered by, most recent or frequent first.
+    fn extract_sort(query: &str) -> (String, Option<u32>) {
+        let mut sort = None;
+        let query = sort_regex().replace_all(query, |caps: &regex::Captures| {
+            match &caps[2] {
+                "last_cooked" => sort = Some(slot::LAST_COOKED),
+                "times_cooked" => sort = Some(slot::TIMES_COOKED),
+                other => warn!("Ignoring unknown sort order {other:?}"),
+            }
+            String::from(&caps[1])
+        });
+
+        (query.into_owned(), sort)
+    }
+
+    /// Rewrites the values of any tag and exact filters (including custom `filter` fields) in
+    /// `query` to the form they're indexed in: tags are canonicalized (e.g. `tag:Veg` becomes
+    /// `tag:vegetarian`, given that alias), while other filters are normalized.
+    fn normalize_filters(&self, query: &str) -> String {
+        filter_regex()
+            .replace_all(query, |caps: &regex::Captures| {
+                let field = &caps[2];
+                let value = caps[3].trim_matches('"');
+                let value = match field {
+                    "tag" | "tag_is" => self.aliases.canonicalize(value),
+                    "site" => String::from(normalize_filter(value).trim_start_matches("www.")),
+                    "category_is" | "source_is" => normalize_filter(value),
+                    field if self.is_filter_field(field) => normalize_filter(value),
+                    _ => return String::from(&caps[0]),
+                };
+                if value.chars().all(char::is_alphanumeric) {
+                    format!("{}{field}:{value}", &caps[1])
+                } else {
+                    format!(r#"{}{field}:"{value}""#, &caps[1])
+                }
+            })
+            .into_owned()
+    }
+
+    /// The domains of the sources, e.g. `seriouseats.com`.
+    pub fn sites(&self) -> impl Iterator<Item = (String, usize)> {
+        self.sites.facets().into_iter()
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item = (String, usize)> {
+        self.sources.facets().into_iter()
+    }
+
+    pub fn tags(&self) -> impl Iterator<Item = (String, BTreeMap<String, usize>)> {
+        self.tagger.namespaces().into_iter()
+    }
+
+    /// Rewrites each word of `query` which has synonyms, whether bare or in a free-text field,
+    /// into an `OR` of the word and its synonyms, so `ingredient:scallion` becomes
+    /// `(ingredient:scallion OR ingredient:"green onion")`. Excluding a word (`-scallion`)
+    /// excludes its synonyms too. Phrases, wildcards, required (`+`) words and exact filters are
+    /// left as they are.
+    ///
+    /// xapian-rs doesn't expose the query parser's own synonym expansion (`FLAG_AUTO_SYNONYMS`,
+    /// which needs the parser to be given the database), so the query is expanded before parsing.
+    fn expand_synonyms(&self, query: &str) -> String {
+        query
+            .split('"')
+            .enumerate()
+            .map(|(i, part)| {
+                // Every other part is within a phrase
+                if i % 2 == 1 {
+                    return Cow::Borrowed(part);
+                }
+
+                synonym_regex().replace_all(part, |caps: &regex::Captures| {
+                    let (sign, field, word) = (&caps[2], caps.get(3), &caps[4]);
+                    // Words run up against a phrase's quotes, or followed by a wildcard, are kept
+                    let bounded = (i == 0 || !caps[1].is_empty())
+                        && part[caps.get(0).unwrap().end()..]
+                            .chars()
+                            .next()
+                            .is_none_or(|c| c.is_whitespace() || c == ')');
+                    let synonyms = self
+                        .synonyms
+                        .get(&word.to_lowercase())
+                        .filter(|synonyms| !synonyms.is_empty())
+                        .filter(|_| bounded && sign != "+")
+                        .filter(|_| {
+                            field.is_none_or(|f| self.text_fields.iter().any(|t| t == f.as_str()))
+                        });
+                    let Some(synonyms) = synonyms else {
+                        return String::from(&caps[0]);
+                    };
+
+                    let prefix = field.map_or(String::new(), |f| format!("{}:", f.as_str()));
+                    let alternatives = std::iter::once(String::from(word))
+                        .chain(synonyms.iter().map(|s| {
+                            if s.contains(char::is_whitespace) {
+                                format!(r#""{s}""#)
+                            } else {
+                                s.clone()
+                            }
+                        }))
+                        .map(|alternative| format!("{sign}{prefix}{alternative}"))
+                        .collect::<Vec<_>>();
+                    if sign == "-" {
+                        format!("{}{}", &caps[1], alternatives.join(" "))
+                    } else {
+                        format!("{}({})", &caps[1], alternatives.join(" OR "))
+                    }
+                })
+            })
+            .collect::<Vec<_>>()
+            .join("\"")
+    }
+
+    /// The documents indexed with every one of `terms`.
+    fn find(&mut self, terms: &[String], size: u32) -> Vec<xapian::Document> {
+        let Some(query) = terms
+            .iter()
+            .map(|term| xapian::Query::term(term, None, None))
+            .reduce(|a, b| xapian::Query::combine(xapian::Operator::And, a, b))
+        else {
+            return Vec::new();
+        };
+
+        self.enquire.set_query(query, None);
+        self.enquire
+            .mset(0, size, None, None)
+            .matches()
+            .map(|m| m.document())
+            .collect()
+    }
+
+    /// The documents matching `query` and every one of `filters`, from the `start`th match.
+    fn search(
+        &mut self,
+        query: impl AsRef<str>,
+        filters: &Filters,
+        start: u32,
+        size: u32,
+    ) -> Vec<xapian::Document> {
         self.categorizer.reset();
+        self.librarian.reset();
+        self.linguist.reset();
+        self.rater.reset();
+        self.sites.reset();
+        self.sources.reset();
         self.tagger.reset();
-        let query = self.query_parser.parse_query(query, None, "");
+        for (_, counter) in &self.field_counters {
+            counter.reset();
+        }
+
+        let (query, sort) = Self::extract_sort(query.as_ref());
+        let query = Self::expand_rating_ranges(&query);
+        let query = self.normalize_filters(&query);
+        let (query, language) = Self::normalize_languages(&query);
+        let language =
+            language.or_else(|| filters.values(&Filter::Language).find_map(Language::parse));
+        self.query_parser
+            .set_stemmer(language.unwrap_or(self.language).stemmer());
+        let query = self.expand_synonyms(&query);
+
+        // The filters are applied to the parsed query, so that its syntax can't affect them
+        let query = match (query.trim().is_empty(), self.filter_query(filters)) {
+            (true, Some(filter)) => {
+                xapian::Query::combine(xapian::Operator::Filter, xapian::Query::match_all(), filter)
+            }
+            (false, Some(filter)) => xapian::Query::combine(
+                xapian::Operator::Filter,
+                self.query_parser.parse_query(query, None, ""),
+                filter,
+            ),
+            (_, None) => self.query_parser.parse_query(query, None, ""),
+        };
         self.enquire.set_query(query, None);
-        self.enquire.mset(start, size, self.db.doc_count(), None)
+
+        let Some(slot) = sort else {
+            return self
+                .enquire
+                .mset(start, size, self.db.doc_count(), None)
+                .matches()
+                .map(|m| m.document())
+                .collect();
+        };
+
+        // xapian-rs can't sort by value, so every match is fetched (in order of relevance), then
+        // sorted by the slot, whose values sort as strings. Recipes without one come last.
+        let mut documents = self
+            .enquire
+            .mset(0, self.db.doc_count(), self.db.doc_count(), None)
+            .matches()
+            .map(|m| m.document())
+            .collect::<Vec<_>>();
+        documents.sort_by_cached_key(|doc| {
+            std::cmp::Reverse(doc.value::<String>(slot).and_then(Result::ok))
+        });
+        documents
+            .into_iter()
+            .skip(start as usize)
+            .take(size as usize)
+            .collect()
     }
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct SearchResult {
     categories: BTreeMap<String, usize>,
+    fields: BTreeMap<String, BTreeMap<String, usize>>,
+    languages: BTreeMap<String, usize>,
+    libraries: BTreeMap<String, usize>,
     matches: Vec<Recipe>,
-    tags: BTreeMap<String, usize>,
+    ratings: BTreeMap<String, usize>,
+    favorites: usize,
+    sites: BTreeMap<String, usize>,
+    sources: BTreeMap<String, usize>,
+    /// Grouped by namespace
+    tags: BTreeMap<String, BTreeMap<String, usize>>,
 }
 
 impl SearchResult {
     pub fn new(
         categories: impl IntoIterator<Item = (String, usize)>,
+        languages: impl IntoIterator<Item = (String, usize)>,
+        libraries: impl IntoIterator<Item = (String, usize)>,
         matches: impl IntoIterator<Item = Recipe>,
-        tags: impl IntoIterator<Item = (String, usize)>,
+        tags: impl IntoIterator<Item = (String, BTreeMap<String, usize>)>,
     ) -> Self {
         Self {
             categories: categories.into_iter().collect(),
+            fields: Default::default(),
+            languages: languages.into_iter().collect(),
+            libraries: libraries.into_iter().collect(),
             matches: matches.into_iter().collect(),
+            ratings: Default::default(),
+            favorites: 0,
+            sites: Default::default(),
+            sources: Default::default(),
             tags: tags.into_iter().collect(),
         }
     }
@@ -415,11 +1440,388 @@ impl SearchResult {
         &self.categories
     }
 
+    /// How many of the matches are favorites.
+    pub fn favorites(&self) -> usize {
+        self.favorites
+    }
+
+    /// The value counts of each custom field which is a facet, by field name.
+    pub fn fields(&self) -> &BTreeMap<String, BTreeMap<String, usize>> {
+        &self.fields
+    }
+
+    pub fn languages(&self) -> &BTreeMap<String, usize> {
+        &self.languages
+    }
+
+    pub fn libraries(&self) -> &BTreeMap<String, usize> {
+        &self.libraries
+    }
+
+    pub fn with_fields(
+        self,
+        fields: impl IntoIterator<Item = (String, BTreeMap<String, usize>)>,
+    ) -> Self {
+        Self {
+            fields: fields.into_iter().collect(),
+            ..self
+        }
+    }
+
     pub fn matches(&self) -> &Vec<Recipe> {
         &self.matches
     }
 
-    pub fn tags(&self) -> &BTreeMap<String, usize> {
+    /// How many of the matches have each rating.
+    pub fn ratings(&self) -> &BTreeMap<String, usize> {
+        &self.ratings
+    }
+
+    pub fn with_ratings(
+        self,
+        ratings: impl IntoIterator<Item = (String, usize)>,
+        favorites: usize,
+    ) -> Self {
+        Self {
+            ratings: ratings.into_iter().collect(),
+            favorites,
+            ..self
+        }
+    }
+
+    /// How many of the matches come from each site (a source URL's domain).
+    pub fn sites(&self) -> &BTreeMap<String, usize> {
+        &self.sites
+    }
+
+    /// How many of the matches come from each source, by name.
+    pub fn sources(&self) -> &BTreeMap<String, usize> {
+        &self.sources
+    }
+
+    pub fn with_sources(
+        self,
+        sources: impl IntoIterator<Item = (String, usize)>,
+        sites: impl IntoIterator<Item = (String, usize)>,
+    ) -> Self {
+        Self {
+            sources: sources.into_iter().collect(),
+            sites: sites.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// The tag counts, grouped by namespace (those without one being under `""`).
+    pub fn tags(&self) -> &BTreeMap<String, BTreeMap<String, usize>> {
         &self.tags
     }
 }
+
+/// A field the search results can be filtered on exactly, given separately from the query (e.g.
+/// by the search page's facets).
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Filter {
+    Category,
+    Favorite,
+    Language,
+    Library,
+    /// Ratings of at least the value
+    Rating,
+    Site,
+    Source,
+    Tag,
+    /// A custom field, by name
+    Field(String),
+}
+
+impl Filter {
+    const ALL: [Self; 8] = [
+        Self::Category,
+        Self::Favorite,
+        Self::Language,
+        Self::Library,
+        Self::Rating,
+        Self::Site,
+        Self::Source,
+        Self::Tag,
+    ];
+
+    /// Custom fields' URL parameters are their names with this prefix, e.g. `field.diet`, so they
+    /// can't be mistaken for other parameters.
+    const FIELD_PARAM_PREFIX: &'static str = "field.";
+
+    /// Looks up a filter by its URL parameter.
+    pub fn from_param(param: impl AsRef<str>) -> Option<Self> {
+        let param = param.as_ref();
+        if let Some(name) = param.strip_prefix(Self::FIELD_PARAM_PREFIX) {
+            return Some(Self::Field(String::from(name))).filter(|_| !name.is_empty());
+        }
+        Self::ALL.into_iter().find(|filter| filter.param() == param)
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            Self::Category => "Category",
+            Self::Favorite => "Favorite",
+            Self::Language => "Language",
+            Self::Library => "Library",
+            Self::Rating => "Minimum rating",
+            Self::Site => "Site",
+            Self::Source => "Source",
+            Self::Tag => "Tag",
+            Self::Field(name) => name,
+        }
+    }
+
+    /// The URL parameter holding the filter's values, e.g. `tag` in `?tag=vegan&tag=quick`.
+    pub fn param(&self) -> String {
+        String::from(match self {
+            Self::Category => "category",
+            Self::Favorite => "favorite",
+            Self::Language => "lang",
+            Self::Library => "library",
+            Self::Rating => "rating",
+            Self::Site => "site",
+            Self::Source => "source",
+            Self::Tag => "tag",
+            Self::Field(name) => return format!("{}{name}", Self::FIELD_PARAM_PREFIX),
+        })
+    }
+}
+
+/// The filters applied to a search, each of which narrows the results. Several values for the same
+/// filter match any of them, except for tags, where each must match.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Filters(std::collections::BTreeSet<(Filter, String)>);
+
+impl Filters {
+    /// Collects the filters among (URL) parameters, ignoring any others.
+    pub fn from_params<K: AsRef<str>, V: AsRef<str>>(
+        params: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        Self(
+            params
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    // Quotes would end a free-text field's phrase early
+                    let value = value.as_ref().replace('"', "");
+                    let value = value.trim();
+                    Some((Filter::from_param(key)?, String::from(value)))
+                        .filter(|_| !value.is_empty())
+                })
+                .collect(),
+        )
+    }
+
+    pub fn contains(&self, filter: &Filter, value: impl AsRef<str>) -> bool {
+        self.0
+            .contains(&(filter.clone(), String::from(value.as_ref())))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Filter, &str)> {
+        self.0
+            .iter()
+            .map(|(filter, value)| (filter, value.as_str()))
+    }
+
+    /// The values given for `filter`.
+    pub fn values<'f>(&'f self, filter: &'f Filter) -> impl Iterator<Item = &'f str> {
+        self.iter()
+            .filter(move |(f, _)| *f == filter)
+            .map(|(_, value)| value)
+    }
+
+    pub fn with(&self, filter: Filter, value: impl Into<String>) -> Self {
+        let mut filters = self.clone();
+        filters.0.insert((filter, value.into()));
+        filters
+    }
+
+    pub fn without(&self, filter: &Filter, value: impl AsRef<str>) -> Self {
+        let mut filters = self.clone();
+        filters
+            .0
+            .remove(&(filter.clone(), String::from(value.as_ref())));
+        filters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::Library;
+
+    fn indexer(fields: &[(&str, FieldKind)]) -> Indexer {
+        let mut config = crate::config::Search::default();
+        for (name, kind) in fields {
+            let field = crate::config::Field {
+                kind: *kind,
+                facet: true,
+            };
+            config.fields.insert(String::from(*name), field);
+        }
+        let libraries = Libraries::new([Library::new("default", "/nonexistent")]).unwrap();
+        let (_, requests) = channel::bounded(1);
+        let (responses, _) = channel::bounded(1);
+        Indexer::new(libraries, config, requests, responses)
+    }
+
+    fn recipe(frontmatter: &str) -> Recipe {
+        Recipe::parse(format!(
+            "---\ntitle: Chili\ncategory: Mains\n{frontmatter}---\n\nA hearty chili.\n"
+        ))
+    }
+
+    #[test]
+    fn filter_fields_match_regardless_of_case() {
+        let mut indexer = indexer(&[("diet", FieldKind::Filter)]);
+        indexer.index_recipe("chili.md", &recipe("diet: Low  Carb\n"), &[]);
+
+        for query in [
+            r#"diet:"low carb""#,
+            r#"diet:"Low Carb""#,
+            r#"diet:"LOW   CARB""#,
+        ] {
+            assert_eq!(
+                indexer
+                    .searcher
+                    .search(query, &Filters::default(), 0, 10)
+                    .len(),
+                1,
+                "{query}"
+            );
+        }
+        assert_eq!(
+            indexer
+                .searcher
+                .search("diet:vegan", &Filters::default(), 0, 10)
+                .len(),
+            0
+        );
+    }
+
+    #[test]
+    fn filters_apply_regardless_of_query_syntax() {
+        let mut indexer = indexer(&[("diet", FieldKind::Filter)]);
+        indexer.index_recipe("chili.md", &recipe("diet: Low  Carb\n"), &[]);
+
+        let low_carb = Filters::from_params([("field.diet", "low carb")]);
+        let vegan = Filters::from_params([("field.diet", r#"vegan" OR chili"#)]);
+        for query in ["", "chili", "chili OR", "(chili"] {
+            assert_eq!(
+                indexer.searcher.search(query, &low_carb, 0, 10).len(),
+                1,
+                "{query}"
+            );
+            assert_eq!(
+                indexer.searcher.search(query, &vegan, 0, 10).len(),
+                0,
+                "{query}"
+            );
+        }
+    }
+
+    #[test]
+    fn synonyms_expand_words_and_free_text_fields() {
+        let mut indexer = indexer(&[("diet", FieldKind::Filter)]);
+        let synonyms = vec![String::from("green onion"), String::from("spring onion")];
+        indexer
+            .searcher
+            .synonyms
+            .insert(String::from("scallion"), synonyms);
+
+        for (query, expanded) in [
+            (
+                "Scallion pancakes",
+                r#"(Scallion OR "green onion" OR "spring onion") pancakes"#,
+            ),
+            (
+                "ingredient:scallion",
+                r#"(ingredient:scallion OR ingredient:"green onion" OR ingredient:"spring onion")"#,
+            ),
+            (
+                "soup -scallion",
+                r#"soup -scallion -"green onion" -"spring onion""#,
+            ),
+            (
+                "(scallion OR leek)",
+                r#"((scallion OR "green onion" OR "spring onion") OR leek)"#,
+            ),
+        ] {
+            assert_eq!(indexer.searcher.expand_synonyms(query), expanded);
+        }
+
+        for query in [
+            r#""scallion pancakes""#,
+            r#""pancakes"scallion"#,
+            "+scallion",
+            "scallion*",
+            "diet:scallion",
+            "slug:scallion",
+        ] {
+            assert_eq!(indexer.searcher.expand_synonyms(query), query);
+        }
+    }
+
+    #[test]
+    fn tags_containing_commas_are_single_facets() {
+        let mut indexer = indexer(&[]);
+        let frontmatter = "tags: [\"salt, pepper\", weeknight]\n";
+        indexer.index_recipe("chili.md", &recipe(frontmatter), &[]);
+
+        assert_eq!(
+            indexer
+                .searcher
+                .search("chili", &Filters::default(), 0, 10)
+                .len(),
+            1
+        );
+        let tags = indexer.searcher.tags().collect::<BTreeMap<_, _>>();
+        let expected = BTreeMap::from([(
+            String::new(),
+            BTreeMap::from([
+                (String::from("salt, pepper"), 1),
+                (String::from("weeknight"), 1),
+            ]),
+        )]);
+        assert_eq!(tags, expected);
+
+        let query = r#"tag_is:"salt, pepper""#;
+        assert_eq!(
+            indexer
+                .searcher
+                .search(query, &Filters::default(), 0, 10)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn rating_ranges_expand_to_each_rating() {
+        let expand = Searcher::expand_rating_ranges;
+        assert_eq!(expand("rating:..2"), "(rating:1 OR rating:2)");
+        assert_eq!(expand("rating:5.."), "(rating:5)");
+        assert_eq!(expand("rating:2..3"), "(rating:2 OR rating:3)");
+        assert_eq!(
+            expand("rating:.."),
+            "(rating:1 OR rating:2 OR rating:3 OR rating:4 OR rating:5)"
+        );
+        assert_eq!(expand("chili (rating:4.."), "chili ((rating:4 OR rating:5)");
+    }
+
+    #[test]
+    fn invalid_rating_ranges_match_nothing_or_are_left_alone() {
+        let expand = Searcher::expand_rating_ranges;
+        // Backwards ranges are empty
+        assert_eq!(expand("rating:4..2"), "rating:0");
+        // Ratings outside of 1 to 5, and ranges within other words, aren't ranges
+        assert_eq!(expand("rating:0..3"), "rating:0..3");
+        assert_eq!(expand("rating:6.."), "rating:6..");
+        assert_eq!(expand("xrating:..2"), "xrating:..2");
+        assert_eq!(expand("rating:3"), "rating:3");
+    }
+}
@@ -0,0 +1,568 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Write},
+    fs, io,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+pub const AISLES_FILE_NAME: &str = "_aisles.yaml";
+pub const FILE_NAME: &str = "_shopping-list.md";
+const OTHER_AISLE: &str = "Other";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Dimension {
+    Count,
+    Mass,
+    Volume,
+}
+
+#[derive(Debug, PartialEq)]
+struct UnitDef {
+    names: &'static [&'static str],
+    dimension: Dimension,
+    factor: f64,
+}
+
+/// Known units, along with their conversion factor to the base unit of their dimension (grams or
+/// millilitres). The first name is used for display.
+const UNITS: &[UnitDef] = &[
+    UnitDef {
+        names: &["tsp", "teaspoon", "teaspoons", "t"],
+        dimension: Dimension::Volume,
+        factor: 4.92892,
+    },
+    UnitDef {
+        names: &["tbsp", "tablespoon", "tablespoons", "tbs", "T"],
+        dimension: Dimension::Volume,
+        factor: 14.7868,
+    },
+    UnitDef {
+        names: &["cup", "cups", "c"],
+        dimension: Dimension::Volume,
+        factor: 236.588,
+    },
+    UnitDef {
+        names: &["pint", "pints", "pt"],
+        dimension: Dimension::Volume,
+        factor: 473.176,
+    },
+    UnitDef {
+        names: &["quart", "quarts", "qt"],
+        dimension: Dimension::Volume,
+        factor: 946.353,
+    },
+    UnitDef {
+        names: &[
+            "ml",
+            "millilitre",
+            "millilitres",
+            "milliliter",
+            "milliliters",
+        ],
+        dimension: Dimension::Volume,
+        factor: 1.0,
+    },
+    UnitDef {
+        names: &["l", "litre", "litres", "liter", "liters"],
+        dimension: Dimension::Volume,
+        factor: 1000.0,
+    },
+    UnitDef {
+        names: &["g", "gram", "grams"],
+        dimension: Dimension::Mass,
+        factor: 1.0,
+    },
+    UnitDef {
+        names: &["kg", "kilogram", "kilograms"],
+        dimension: Dimension::Mass,
+        factor: 1000.0,
+    },
+    UnitDef {
+        names: &["oz", "ounce", "ounces"],
+        dimension: Dimension::Mass,
+        factor: 28.3495,
+    },
+    UnitDef {
+        names: &["lb", "lbs", "pound", "pounds"],
+        dimension: Dimension::Mass,
+        factor: 453.592,
+    },
+];
+
+fn parse_number(token: &str) -> Option<f64> {
+    const VULGAR: &[(char, f64)] = &[
+        ('¼', 0.25),
+        ('½', 0.5),
+        ('¾', 0.75),
+        ('⅓', 1.0 / 3.0),
+        ('⅔', 2.0 / 3.0),
+        ('⅛', 0.125),
+    ];
+
+    if let Some((whole, fraction)) = VULGAR
+        .iter()
+        .find_map(|(c, v)| token.strip_suffix(*c).map(|whole| (whole, *v)))
+    {
+        return if whole.is_empty() {
+            Some(fraction)
+        } else {
+            whole.parse::<f64>().ok().map(|w| w + fraction)
+        };
+    }
+
+    if let Some((num, denom)) = token.split_once('/') {
+        let (num, denom) = (num.parse::<f64>().ok()?, denom.parse::<f64>().ok()?);
+        return (denom != 0.0).then(|| num / denom);
+    }
+
+    // Ranges such as `2-3` are shopped for at their upper bound
+    if let Some((_, upper)) = token.split_once(['-', '–']) {
+        return parse_number(upper);
+    }
+
+    token.parse::<f64>().ok()
+}
+
+fn format_number(value: f64) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    if rounded.fract() == 0.0 {
+        format!("{rounded:.0}")
+    } else {
+        format!("{rounded}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Amount {
+    value: f64,
+    unit: Option<&'static UnitDef>,
+}
+
+impl Amount {
+    fn base_value(&self) -> f64 {
+        self.value * self.unit.map_or(1.0, |u| u.factor)
+    }
+
+    fn dimension(&self) -> Dimension {
+        self.unit.map_or(Dimension::Count, |u| u.dimension)
+    }
+}
+
+impl Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_number(self.value))?;
+        if let Some(unit) = self.unit {
+            write!(f, " {}", unit.names[0])?;
+        }
+        Ok(())
+    }
+}
+
+/// A single ingredient line, split into an optional amount and the item being measured.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ingredient {
+    amount: Option<Amount>,
+    item: String,
+}
+
+impl Ingredient {
+    pub fn parse(line: impl AsRef<str>) -> Self {
+        let line = line.as_ref().trim();
+        let mut tokens = line.split_whitespace().peekable();
+
+        let mut value = None;
+        while let Some(number) = tokens.peek().and_then(|t| parse_number(t)) {
+            value = Some(value.unwrap_or(0.0) + number);
+            tokens.next();
+        }
+
+        let unit = value.and_then(|_| {
+            let unit = tokens.peek().and_then(|t| {
+                let t = t.trim_end_matches('.');
+                UNITS.iter().find(|u| {
+                    u.names
+                        .iter()
+                        .any(|n| *n == t || (n.len() > 1 && n.eq_ignore_ascii_case(t)))
+                })
+            });
+            if unit.is_some() {
+                tokens.next();
+            }
+            unit
+        });
+
+        let rest = tokens.collect::<Vec<_>>().join(" ");
+        let rest = rest.strip_prefix("of ").unwrap_or(&rest);
+        let item = rest
+            .split([',', '('])
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+
+        match value {
+            Some(value) if !item.is_empty() => Self {
+                amount: Some(Amount { value, unit }),
+                item,
+            },
+            _ => Self {
+                amount: None,
+                item: line.to_lowercase(),
+            },
+        }
+    }
+
+    pub fn item(&self) -> &str {
+        &self.item
+    }
+}
+
+/// Maps aisle names to keywords which, when present in an ingredient, place it in that aisle.
+/// Defaults can be overridden by an `_aisles.yaml` file in the recipe directory.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Aisles(BTreeMap<String, Vec<String>>);
+
+impl Default for Aisles {
+    fn default() -> Self {
+        let aisles: &[(&str, &[&str])] = &[
+            (
+                "Produce",
+                &[
+                    "apple", "basil", "carrot", "celery", "cilantro", "garlic", "ginger", "lemon",
+                    "lettuce", "lime", "onion", "parsley", "pepper", "potato", "scallion",
+                    "shallot", "spinach", "tomato",
+                ],
+            ),
+            (
+                "Dairy",
+                &[
+                    "butter", "cheese", "cream", "egg", "milk", "parmesan", "yogurt",
+                ],
+            ),
+            (
+                "Meat & Seafood",
+                &[
+                    "bacon", "beef", "chicken", "fish", "ham", "lamb", "pork", "sausage", "shrimp",
+                    "turkey",
+                ],
+            ),
+            (
+                "Baking",
+                &[
+                    "baking powder",
+                    "baking soda",
+                    "chocolate",
+                    "cornstarch",
+                    "flour",
+                    "sugar",
+                    "vanilla",
+                    "yeast",
+                ],
+            ),
+            (
+                "Spices",
+                &[
+                    "cinnamon", "cumin", "nutmeg", "oregano", "paprika", "salt", "thyme",
+                ],
+            ),
+            (
+                "Pantry",
+                &[
+                    "beans", "broth", "oil", "pasta", "rice", "sauce", "stock", "vinegar",
+                ],
+            ),
+        ];
+
+        Self(
+            aisles
+                .iter()
+                .map(|(aisle, keywords)| {
+                    (
+                        String::from(*aisle),
+                        keywords.iter().map(|k| String::from(*k)).collect(),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Aisles {
+    pub fn load(recipe_dir: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::File::open(recipe_dir.as_ref().join(AISLES_FILE_NAME)) {
+            Ok(f) => serde_yaml::from_reader(f)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn aisle_for(&self, item: &str) -> &str {
+        self.0
+            .iter()
+            .find(|(_, keywords)| keywords.iter().any(|k| contains_words(item, k)))
+            .map_or(OTHER_AISLE, |(aisle, _)| aisle.as_str())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    item: String,
+    amount: Option<Amount>,
+}
+
+impl Entry {
+    fn merge(&mut self, other: &Ingredient) -> bool {
+        let merged = match (&mut self.amount, &other.amount) {
+            (None, None) => true,
+            (Some(mine), Some(theirs)) if mine.dimension() == theirs.dimension() => {
+                let base = mine.base_value() + theirs.base_value();
+                mine.value = base / mine.unit.map_or(1.0, |u| u.factor);
+                true
+            }
+            _ => false,
+        };
+
+        // Several of an item are named in the plural, e.g. "1 egg" and "2 eggs" make "3 eggs"
+        if merged && other.item.len() > self.item.len() {
+            self.item.clone_from(&other.item);
+        }
+        merged
+    }
+}
+
+/// Whether `a` and `b` name the same item, allowing either to be a simple plural of the other.
+fn same_item(a: &str, b: &str) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    long.strip_prefix(short)
+        .is_some_and(|suffix| ["", "s", "es"].contains(&suffix))
+}
+
+/// Whether `text` contains `words` as whole words (allowing simple plurals), ignoring case, so that
+/// "egg" is found in "2 eggs" but not in "eggplant", and "salt" isn't found in "unsalted butter".
+pub fn contains_words(text: &str, words: &str) -> bool {
+    let (text, words) = (text.to_lowercase(), words.trim().to_lowercase());
+    let is_word_char = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    !words.is_empty()
+        && text.match_indices(&words).any(|(start, _)| {
+            let rest = &text[start + words.len()..];
+            !is_word_char(text[..start].chars().next_back())
+                && ["", "s", "es"].iter().any(|suffix| {
+                    rest.strip_prefix(suffix)
+                        .is_some_and(|rest| !is_word_char(rest.chars().next()))
+                })
+        })
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(amount) = self.amount {
+            write!(f, "{amount} ")?;
+        }
+        f.write_str(&self.item)
+    }
+}
+
+/// An aggregated list of ingredients across several recipes, grouped by aisle.
+#[derive(Clone, Debug, Default)]
+pub struct ShoppingList {
+    recipes: Vec<(String, String)>,
+    aisles: BTreeMap<String, Vec<Entry>>,
+}
+
+impl ShoppingList {
+    pub fn new<'r>(
+        recipes: impl IntoIterator<Item = &'r crate::recipe::Recipe>,
+        aisles: &Aisles,
+    ) -> Self {
+        let mut list = Self::default();
+
+        for recipe in recipes {
+            if let (Some(title), Some(id)) = (recipe.title(), recipe.id()) {
+                list.recipes.push((String::from(title), id));
+            }
+
+            for line in recipe.ingredient_list() {
+                list.add(Ingredient::parse(line), aisles);
+            }
+        }
+
+        list
+    }
+
+    fn add(&mut self, ingredient: Ingredient, aisles: &Aisles) {
+        let aisle = String::from(aisles.aisle_for(ingredient.item()));
+        let entries = self.aisles.entry(aisle).or_default();
+
+        if !entries
+            .iter_mut()
+            .filter(|e| same_item(&e.item, &ingredient.item))
+            .any(|e| e.merge(&ingredient))
+        {
+            entries.push(Entry {
+                item: ingredient.item,
+                amount: ingredient.amount,
+            });
+        }
+    }
+
+    pub fn aisles(&self) -> &BTreeMap<String, Vec<Entry>> {
+        &self.aisles
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aisles.is_empty()
+    }
+
+    pub fn recipes(&self) -> &Vec<(String, String)> {
+        &self.recipes
+    }
+
+    /// Renders this list as Markdown. Its recipes are linked by name, or at `base_url` if given, so
+    /// that they can be followed from outside of Pantry.
+    pub fn to_markdown(&self, base_url: Option<&Url>) -> String {
+        let mut markdown = String::from("# Shopping List\n\n");
+
+        for (title, id) in &self.recipes {
+            let _ = match base_url {
+                Some(base_url) => writeln!(markdown, "- [{title}]({})", recipe_url(base_url, id)),
+                None => writeln!(markdown, "- [[{title}]]"),
+            };
+        }
+
+        for (aisle, entries) in &self.aisles {
+            let _ = write!(markdown, "\n## {aisle}\n\n");
+            for entry in entries {
+                let _ = writeln!(markdown, "- [ ] {entry}");
+            }
+        }
+
+        markdown
+    }
+
+    pub fn save(&self, recipe_dir: impl AsRef<Path>, base_url: Option<&Url>) -> io::Result<()> {
+        fs::write(
+            recipe_dir.as_ref().join(FILE_NAME),
+            self.to_markdown(base_url),
+        )
+    }
+}
+
+/// The absolute URL of the recipe identified by `id` (as `library/slug`), when Pantry is served at
+/// `base_url`.
+fn recipe_url(base_url: &Url, id: &str) -> Url {
+    let mut url = base_url.clone();
+    if let Ok(mut segments) = url.path_segments_mut() {
+        segments.pop_if_empty().push("recipe").extend(id.split('/'));
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(lines: &[&str]) -> Vec<String> {
+        let mut list = ShoppingList::default();
+        for line in lines {
+            list.add(Ingredient::parse(line), &Aisles::default());
+        }
+        list.aisles()
+            .values()
+            .flatten()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn sums_the_same_unit() {
+        assert_eq!(
+            list(&["1 cup flour", "2 cups flour, sifted", "½ cup flour"]),
+            ["3.5 cup flour"]
+        );
+    }
+
+    #[test]
+    fn merges_singular_and_plural_items() {
+        assert_eq!(list(&["1 egg", "2 eggs"]), ["3 eggs"]);
+        assert_eq!(list(&["2 eggs", "1 egg"]), ["3 eggs"]);
+        assert_eq!(list(&["1 tomato", "2 tomatoes"]), ["3 tomatoes"]);
+        assert_eq!(list(&["1 egg", "1 eggplant"]), ["1 egg", "1 eggplant"]);
+    }
+
+    #[test]
+    fn aisles_match_whole_words() {
+        let aisles = Aisles::default();
+        assert_eq!(aisles.aisle_for("eggs"), "Dairy");
+        assert_eq!(aisles.aisle_for("large egg"), "Dairy");
+        assert_eq!(aisles.aisle_for("eggplant"), OTHER_AISLE);
+        assert_eq!(aisles.aisle_for("olive oil"), "Pantry");
+        assert_eq!(aisles.aisle_for("aluminum foil"), OTHER_AISLE);
+        assert_eq!(aisles.aisle_for("Baking Soda"), "Baking");
+    }
+
+    #[test]
+    fn finds_whole_words() {
+        assert!(contains_words("2 eggs, beaten", "egg"));
+        assert!(contains_words("add the green onions", "green onion"));
+        assert!(!contains_words("unsalted butter", "salt"));
+        assert!(!contains_words("bring to a boil", "oil"));
+        assert!(!contains_words("anything", ""));
+    }
+
+    #[test]
+    fn converts_compatible_units() {
+        assert_eq!(list(&["1 cup milk", "16 tbsp milk"]), ["2 cup milk"]);
+    }
+
+    #[test]
+    fn keeps_incompatible_units_separate() {
+        assert_eq!(
+            list(&["200 g butter", "2 tbsp butter", "1 butter"]),
+            ["200 g butter", "2 tbsp butter", "1 butter"]
+        );
+    }
+
+    #[test]
+    fn passes_unparseable_quantities_through() {
+        assert_eq!(
+            list(&[
+                "Salt to taste",
+                "a pinch of salt",
+                "Salt to taste",
+                "2 g salt"
+            ]),
+            ["salt to taste", "a pinch of salt", "2 g salt"]
+        );
+        assert_eq!(
+            Ingredient::parse("A handful of basil"),
+            Ingredient {
+                amount: None,
+                item: String::from("a handful of basil"),
+            }
+        );
+    }
+
+    #[test]
+    fn markdown_links_recipes_at_base_url() {
+        let chili = crate::recipe::Recipe::parse(
+            "---\ntitle: Chili\ncategory: Mains\n---\n\n## Ingredients\n\n- 1 onion\n",
+        )
+        .with_library("family");
+        let list = ShoppingList::new([&chili], &Aisles::default());
+
+        assert!(list.to_markdown(None).contains("- [[Chili]]\n"));
+        for base_url in ["https://example.com/pantry", "https://example.com/pantry/"] {
+            let base_url = Url::parse(base_url).unwrap();
+            assert!(list
+                .to_markdown(Some(&base_url))
+                .contains("- [Chili](https://example.com/pantry/recipe/family/chili)\n"));
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+
+/// The directory (relative to a library) where Pantry keeps its own data. Being dot-prefixed, it's
+/// never indexed.
+pub const DATA_DIR: &str = ".pantry";
+
+/// A previous version of a recipe file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Snapshot {
+    hash: String,
+    taken: OffsetDateTime,
+}
+
+impl Snapshot {
+    fn parse(line: &str) -> Option<Self> {
+        let (timestamp, hash) = line.split_once('\t')?;
+        Some(Self {
+            hash: String::from(hash.trim()),
+            taken: OffsetDateTime::from_unix_timestamp(timestamp.parse().ok()?).ok()?,
+        })
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(8)]
+    }
+
+    /// When the snapshot was taken, e.g. "2024-03-01 18:30 UTC".
+    pub fn taken(&self) -> String {
+        format!(
+            "{} {:02}:{:02} UTC",
+            self.taken.date(),
+            self.taken.hour(),
+            self.taken.minute()
+        )
+    }
+}
+
+/// A store of snapshots for the recipes in a library, kept under `.pantry/snapshots`. File
+/// contents are stored once, named by their SHA-256 hash, and each recipe has an append-only log
+/// of the hashes it has had, mirroring its path within the library.
+#[derive(Clone, Debug)]
+pub struct Snapshots {
+    library: PathBuf,
+}
+
+impl Snapshots {
+    pub fn new(library: impl AsRef<Path>) -> Self {
+        Self {
+            library: PathBuf::from(library.as_ref()),
+        }
+    }
+
+    fn dir(&self) -> PathBuf {
+        self.library.join(DATA_DIR).join("snapshots")
+    }
+
+    fn log_path(&self, path: &Path) -> Option<PathBuf> {
+        let relative = path.strip_prefix(&self.library).ok()?;
+        let mut log = self.dir().join("log").join(relative);
+        log.as_mut_os_string().push(".log");
+        Some(log)
+    }
+
+    fn object_path(&self, hash: &str) -> Option<PathBuf> {
+        (hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| self.dir().join("objects").join(hash))
+    }
+
+    /// Records the current contents of the recipe at `path`, unless they're unchanged since the
+    /// last snapshot. Returns whether a snapshot was taken.
+    pub fn capture(&self, path: impl AsRef<Path>) -> io::Result<bool> {
+        let path = path.as_ref();
+        let Some(log_path) = self.log_path(path) else {
+            return Ok(false);
+        };
+
+        let contents = fs::read(path)?;
+        let hash = Sha256::digest(&contents)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+
+        if self.list(path)?.first().is_some_and(|s| s.hash == hash) {
+            return Ok(false);
+        }
+
+        self.init()?;
+        let object = self.dir().join("objects").join(&hash);
+        if !object.exists() {
+            fs::create_dir_all(self.dir().join("objects"))?;
+            fs::write(object, &contents)?;
+        }
+
+        if let Some(parent) = log_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut log = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path)?;
+        writeln!(
+            log,
+            "{}\t{hash}",
+            OffsetDateTime::now_utc().unix_timestamp()
+        )?;
+
+        Ok(true)
+    }
+
+    /// Creates the data directory, excluding it from any git repository the library is in.
+    fn init(&self) -> io::Result<()> {
+        let dir = self.library.join(DATA_DIR);
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+            fs::write(dir.join(".gitignore"), "*\n")?;
+        }
+        Ok(())
+    }
+
+    /// Whether any snapshot of the recipe at `path` has been taken.
+    pub fn has_snapshots(&self, path: impl AsRef<Path>) -> bool {
+        self.log_path(path.as_ref())
+            .is_some_and(|log_path| log_path.exists())
+    }
+
+    /// The snapshots of the recipe at `path`, most recent first.
+    pub fn list(&self, path: impl AsRef<Path>) -> io::Result<Vec<Snapshot>> {
+        let Some(log_path) = self.log_path(path.as_ref()) else {
+            return Ok(Vec::new());
+        };
+
+        let mut snapshots = match fs::read_to_string(log_path) {
+            Ok(log) => log.lines().filter_map(Snapshot::parse).collect::<Vec<_>>(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        snapshots.reverse();
+        Ok(snapshots)
+    }
+
+    /// The contents of the snapshot with the given hash.
+    pub fn load(&self, hash: impl AsRef<str>) -> io::Result<String> {
+        let path = self
+            .object_path(hash.as_ref())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        fs::read_to_string(path)
+    }
+}
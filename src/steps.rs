@@ -0,0 +1,324 @@
+use crate::{
+    recipe::Recipe,
+    shopping::{self, Ingredient},
+};
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+fn duration_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"(?i)\b(\d+(?:\.\d+)?)(?:\s*(?:-|–|to)\s*\d+(?:\.\d+)?)?\s*(hours?|hrs?|h|minutes?|mins?|seconds?|secs?)\b",
+        )
+        .unwrap()
+    })
+}
+
+fn temperature_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"\b(\d{2,3})\s*(?:(?:°|[Dd]egrees?)\s*(?:([CcFf]|[Cc]elsius|[Ff]ahrenheit)\b)?|([CF])\b)",
+        )
+        .unwrap()
+    })
+}
+
+/// Formats a number of seconds for display, e.g. "1 h 30 min".
+pub fn humanize(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, (seconds % 3600) / 60, seconds % 60);
+    [(hours, "h"), (minutes, "min"), (seconds, "s")]
+        .into_iter()
+        .filter(|(amount, _)| *amount > 0)
+        .map(|(amount, unit)| format!("{amount} {unit}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Scale {
+    Celsius,
+    Fahrenheit,
+}
+
+/// An oven (or oil, or sugar) temperature mentioned in the text of a step, such as "350°F".
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Temperature {
+    label: String,
+    degrees: u32,
+    scale: Scale,
+}
+
+impl Temperature {
+    /// Finds every temperature in `text`. Temperatures without an explicit scale are assumed to be
+    /// in Fahrenheit when above 250, and Celsius otherwise.
+    pub fn find_all(text: impl AsRef<str>) -> Vec<Self> {
+        temperature_regex()
+            .captures_iter(text.as_ref())
+            .filter_map(|caps| {
+                let degrees = caps[1].parse::<u32>().ok()?;
+                let scale = caps.get(2).or(caps.get(3));
+                let scale = match scale.map(|m| m.as_str().to_lowercase()) {
+                    Some(scale) if scale.starts_with('c') => Scale::Celsius,
+                    Some(_) => Scale::Fahrenheit,
+                    None if degrees > 250 => Scale::Fahrenheit,
+                    None => Scale::Celsius,
+                };
+
+                Some(Self {
+                    label: String::from(caps[0].trim()),
+                    degrees,
+                    scale,
+                })
+            })
+            .collect()
+    }
+
+    pub fn celsius(&self) -> u32 {
+        match self.scale {
+            Scale::Celsius => self.degrees,
+            Scale::Fahrenheit => (self.degrees.saturating_sub(32) * 5) / 9,
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+/// A duration mentioned in the text of a step, such as "simmer 20 minutes".
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Timer {
+    label: String,
+    seconds: u64,
+}
+
+impl Timer {
+    /// Finds every duration in `text`. Ranges ("20-25 minutes") use their lower bound, so the cook
+    /// checks in early, and compound durations ("1 hour 30 minutes") become a single timer.
+    pub fn find_all(text: impl AsRef<str>) -> Vec<Self> {
+        let text = text.as_ref();
+        let mut timers: Vec<(usize, usize, u64)> = Vec::new();
+
+        for caps in duration_regex().captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            let amount = caps[1].parse::<f64>().unwrap_or_default();
+            let unit = caps[2].to_lowercase();
+            let multiplier = if unit.starts_with('h') {
+                3600.0
+            } else if unit.starts_with('m') {
+                60.0
+            } else {
+                1.0
+            };
+            let seconds = (amount * multiplier).round() as u64;
+
+            match timers.last_mut() {
+                Some((_, end, total))
+                    if text[*end..whole.start()]
+                        .trim()
+                        .trim_start_matches("and")
+                        .trim()
+                        .is_empty() =>
+                {
+                    *end = whole.end();
+                    *total += seconds;
+                }
+                _ => timers.push((whole.start(), whole.end(), seconds)),
+            }
+        }
+
+        timers
+            .into_iter()
+            .filter(|(_, _, seconds)| *seconds > 0)
+            .map(|(start, end, seconds)| Self {
+                label: String::from(&text[start..end]),
+                seconds,
+            })
+            .collect()
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn seconds(&self) -> u64 {
+        self.seconds
+    }
+}
+
+/// A single direction from a recipe, along with the ingredients, timers and temperatures it
+/// mentions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Step {
+    section: Option<String>,
+    html: String,
+    ingredients: Vec<String>,
+    temperatures: Vec<Temperature>,
+    timers: Vec<Timer>,
+}
+
+impl Step {
+    pub fn from_recipe(recipe: &Recipe) -> Vec<Self> {
+        let ingredients = recipe
+            .ingredient_list()
+            .into_iter()
+            .map(|line| (Ingredient::parse(&line), line))
+            .collect::<Vec<_>>();
+
+        recipe
+            .sections("directions")
+            .into_iter()
+            .flat_map(|section| {
+                let name = section.name().map(String::from);
+                section
+                    .items()
+                    .clone()
+                    .into_iter()
+                    .map(move |html| (name.clone(), html))
+            })
+            .map(|(section, html)| {
+                let text = scraper::Html::parse_fragment(&html)
+                    .root_element()
+                    .text()
+                    .collect::<String>();
+
+                Self {
+                    section,
+                    ingredients: ingredients
+                        .iter()
+                        .filter(|(ingredient, _)| mentions(&text, ingredient.item()))
+                        .map(|(_, line)| line.clone())
+                        .collect(),
+                    temperatures: Temperature::find_all(&text),
+                    timers: Timer::find_all(&text),
+                    html,
+                }
+            })
+            .collect()
+    }
+
+    pub fn html(&self) -> &str {
+        &self.html
+    }
+
+    pub fn ingredients(&self) -> &Vec<String> {
+        &self.ingredients
+    }
+
+    pub fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+
+    pub fn temperatures(&self) -> &Vec<Temperature> {
+        &self.temperatures
+    }
+
+    pub fn timers(&self) -> &Vec<Timer> {
+        &self.timers
+    }
+}
+
+/// Whether a step mentions an ingredient as whole words, either in full or by its final word (e.g.
+/// "flour" for "all-purpose flour"), ignoring plurals.
+fn mentions(step: &str, item: &str) -> bool {
+    let noun = item.split_whitespace().last().unwrap_or(item);
+    let noun = noun.strip_suffix('s').unwrap_or(noun);
+
+    shopping::contains_words(step, item) || shopping::contains_words(step, noun)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timers(text: &str) -> Vec<(String, u64)> {
+        Timer::find_all(text)
+            .into_iter()
+            .map(|t| (String::from(t.label()), t.seconds()))
+            .collect()
+    }
+
+    fn temperatures(text: &str) -> Vec<(String, u32)> {
+        Temperature::find_all(text)
+            .into_iter()
+            .map(|t| (String::from(t.label()), t.celsius()))
+            .collect()
+    }
+
+    #[test]
+    fn timers_use_the_lower_bound_of_ranges() {
+        assert_eq!(
+            timers("Simmer for 10–12 minutes, until thick."),
+            [(String::from("10–12 minutes"), 600)]
+        );
+        assert_eq!(
+            timers("Bake 20-25 mins"),
+            [(String::from("20-25 mins"), 1200)]
+        );
+        assert_eq!(
+            timers("Rest 2 to 3 hours"),
+            [(String::from("2 to 3 hours"), 7200)]
+        );
+    }
+
+    #[test]
+    fn timers_combine_hours_and_minutes() {
+        assert_eq!(
+            timers("Braise 1 hour 30 minutes, then rest 10 min."),
+            [
+                (String::from("1 hour 30 minutes"), 5400),
+                (String::from("10 min"), 600)
+            ]
+        );
+        assert_eq!(
+            timers("Roast for 2 hrs and 15 mins"),
+            [(String::from("2 hrs and 15 mins"), 8100)]
+        );
+        assert_eq!(timers("Proof 1.5 h"), [(String::from("1.5 h"), 5400)]);
+    }
+
+    #[test]
+    fn temperatures_in_either_scale() {
+        assert_eq!(
+            temperatures("Preheat the oven to 350°F (180 °C)."),
+            [(String::from("350°F"), 176), (String::from("180 °C"), 180)]
+        );
+        assert_eq!(
+            temperatures("Heat oil to 375 degrees Fahrenheit"),
+            [(String::from("375 degrees Fahrenheit"), 190)]
+        );
+        assert_eq!(
+            temperatures("Bake at 200C, or 425 degrees"),
+            [
+                (String::from("200C"), 200),
+                (String::from("425 degrees"), 218)
+            ]
+        );
+    }
+
+    #[test]
+    fn quantities_are_not_timers_or_temperatures() {
+        for text in [
+            "Whisk 2 eggs with 100 g sugar.",
+            "Add 3 cups of flour and 12 cm of ginger.",
+            "Serves 4 to 6 people",
+            "Use a 9x13 inch pan",
+        ] {
+            assert_eq!(timers(text), [], "{text}");
+            assert_eq!(temperatures(text), [], "{text}");
+        }
+    }
+
+    #[test]
+    fn ingredients_are_mentioned_by_whole_words() {
+        assert!(mentions("Whisk the eggs.", "egg"));
+        assert!(mentions("Sift the flour.", "all-purpose flour"));
+        assert!(mentions("Add the Carrot.", "carrots"));
+        assert!(!mentions("Cream the unsalted butter.", "salt"));
+        assert!(!mentions("Bring to a boil.", "oil"));
+    }
+}
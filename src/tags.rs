@@ -0,0 +1,133 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use serde::Deserialize;
+
+pub const FILE_NAME: &str = "_tags.yaml";
+
+/// Normalizes the spelling of `tag`: it's lowercased, runs of whitespace are collapsed, and any
+/// namespace is tidied (so `Cuisine : Thai` becomes `cuisine:thai`).
+pub fn normalize(tag: impl AsRef<str>) -> String {
+    let words = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+    let tag = tag.as_ref().to_lowercase();
+    match tag.split_once(':') {
+        Some((namespace, name)) if !namespace.trim().is_empty() && !name.trim().is_empty() => {
+            format!("{}:{}", words(namespace), words(name))
+        }
+        _ => words(&tag),
+    }
+}
+
+/// Splits a (normalized) tag into its namespace, if any, and name: `cuisine:thai` is in the
+/// `cuisine` namespace, while `weeknight` is in none.
+pub fn split(tag: &str) -> (Option<&str>, &str) {
+    match tag.split_once(':') {
+        Some((namespace, name)) => (Some(namespace), name),
+        None => (None, tag),
+    }
+}
+
+/// Variant spellings of tags, and the canonical tag each stands for, as recorded in `_tags.yaml`
+/// at the root of the recipe directory. The file maps each canonical tag to its variants:
+///
+/// ```yaml
+/// vegetarian: [veg, veggie]
+/// diet:vegan: [vegan, plant-based]
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Aliases(BTreeMap<String, String>);
+
+impl Aliases {
+    pub fn load(recipe_dir: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::File::open(recipe_dir.as_ref().join(FILE_NAME)) {
+            Ok(f) => serde_yaml::from_reader::<_, Option<BTreeMap<String, Variants>>>(f)
+                .map(|aliases| Self::new(aliases.unwrap_or_default()))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn new(aliases: BTreeMap<String, Variants>) -> Self {
+        Self(
+            aliases
+                .into_iter()
+                .flat_map(|(canonical, variants)| {
+                    let canonical = normalize(canonical);
+                    variants
+                        .into_vec()
+                        .into_iter()
+                        .map(move |variant| (normalize(variant), canonical.clone()))
+                })
+                .collect(),
+        )
+    }
+
+    /// The canonical form of `tag`: normalized, and with any alias resolved.
+    pub fn canonicalize(&self, tag: impl AsRef<str>) -> String {
+        let tag = normalize(tag);
+        self.0.get(&tag).cloned().unwrap_or(tag)
+    }
+
+    pub fn is_aliases_file(recipe_dir: impl AsRef<Path>, path: impl AsRef<Path>) -> bool {
+        path.as_ref() == recipe_dir.as_ref().join(FILE_NAME)
+    }
+}
+
+/// A canonical tag's variants, given as a list, or (for a single variant) a string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Variants {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Variants {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Self::One(variant) => vec![variant],
+            Self::Many(variants) => variants,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases(yaml: &str) -> Aliases {
+        Aliases::new(serde_yaml::from_str(yaml).unwrap())
+    }
+
+    #[test]
+    fn normalize_tidies_spelling() {
+        assert_eq!(normalize("Weeknight"), "weeknight");
+        assert_eq!(normalize("  One   Pot "), "one pot");
+        assert_eq!(normalize("Cuisine : Thai"), "cuisine:thai");
+        assert_eq!(
+            normalize("cuisine:South  East Asian"),
+            "cuisine:south east asian"
+        );
+    }
+
+    #[test]
+    fn normalize_keeps_colons_without_a_namespace() {
+        assert_eq!(normalize(":thai"), ":thai");
+        assert_eq!(normalize("Ratio 1:"), "ratio 1:");
+    }
+
+    #[test]
+    fn canonicalize_resolves_aliases() {
+        let aliases = aliases("Vegetarian: [veg, Veggie]\ndiet:vegan: Plant Based\n");
+        assert_eq!(aliases.canonicalize("Veggie"), "vegetarian");
+        assert_eq!(aliases.canonicalize(" veg "), "vegetarian");
+        assert_eq!(aliases.canonicalize("plant  based"), "diet:vegan");
+        assert_eq!(aliases.canonicalize("Vegetarian"), "vegetarian");
+        assert_eq!(aliases.canonicalize("Spicy"), "spicy");
+    }
+
+    #[test]
+    fn split_namespaces() {
+        assert_eq!(split("cuisine:thai"), (Some("cuisine"), "thai"));
+        assert_eq!(split("weeknight"), (None, "weeknight"));
+    }
+}
@@ -1,10 +1,13 @@
-use std::ops::Deref;
+use std::{collections::BTreeMap, ops::Deref};
 
 use askama_axum::Template;
 
 const PLACEHOLDER: &str = "—";
 static LAYOUT: Layout = Layout;
 
+/// A tag among the search results: in full, by its name within its namespace, and its count.
+type TagFacet<'t> = (&'t str, &'t str, usize);
+
 #[derive(Default, Template)]
 #[template(path = "_layout.html")]
 pub struct Layout;
@@ -13,18 +16,37 @@ pub struct Layout;
 #[template(path = "_search_bar.html")]
 pub struct SearchBar {
     query: Option<String>,
+    filters: crate::search::Filters,
 }
 
 impl SearchBar {
     pub fn new(query: impl Into<Option<String>>) -> Self {
         Self {
             query: query.into(),
+            filters: Default::default(),
         }
     }
 
     pub fn query(&self) -> &str {
         self.query.as_deref().unwrap_or_default()
     }
+
+    /// The filters applied to the search, which are kept as the query changes.
+    pub fn filters(&self) -> impl Iterator<Item = (&crate::search::Filter, &str)> {
+        self.filters.iter()
+    }
+
+    /// A link to the search for the current query, with `filters` in place of the current ones.
+    fn url(&self, filters: &crate::search::Filters) -> String {
+        let mut params = url::form_urlencoded::Serializer::new(String::new());
+        if !self.query().is_empty() || filters.is_empty() {
+            params.append_pair("query", self.query());
+        }
+        for (filter, value) in filters.iter() {
+            params.append_pair(&filter.param(), value);
+        }
+        format!("/search?{}", params.finish())
+    }
 }
 
 #[derive(Template)]
@@ -34,6 +56,79 @@ pub struct Recipe<'r> {
     search_bar: SearchBar,
     recipe: crate::recipe::Recipe,
     title: String,
+    steps: Vec<crate::steps::Step>,
+    in_stock: Vec<String>,
+    backlinks: Vec<crate::recipe::Recipe>,
+    dangling: Vec<String>,
+    journal: Vec<crate::journal::Entry>,
+}
+
+impl Recipe<'_> {
+    pub fn active_time(&self) -> String {
+        crate::steps::humanize(
+            self.steps
+                .iter()
+                .flat_map(|step| step.timers())
+                .map(|timer| timer.seconds())
+                .sum(),
+        )
+    }
+
+    /// Each star of the rating control: its number, whether it's filled, and the rating clicking
+    /// it sets (clicking the current rating clears it).
+    pub fn rating_stars(&self) -> Vec<(u8, bool, String)> {
+        let rating = self.recipe.metadata().and_then(|md| md.rating());
+        (1..=5)
+            .map(|stars| {
+                let value = if rating == Some(stars) {
+                    String::new()
+                } else {
+                    stars.to_string()
+                };
+                (stars, rating.is_some_and(|r| stars <= r), value)
+            })
+            .collect()
+    }
+
+    pub fn has_timers(&self) -> bool {
+        self.steps.iter().any(|step| !step.timers().is_empty())
+    }
+
+    pub fn dangling_json(&self) -> String {
+        serde_json::to_string(&self.dangling).unwrap()
+    }
+
+    pub fn in_stock_json(&self) -> String {
+        serde_json::to_string(&self.in_stock).unwrap()
+    }
+
+    pub fn with_inventory(self, inventory: &crate::inventory::Inventory) -> Self {
+        let in_stock = inventory
+            .in_stock()
+            .map(|item| item.name().to_lowercase())
+            .collect();
+
+        Self { in_stock, ..self }
+    }
+
+    /// Today's date, which new journal entries default to.
+    pub fn today(&self) -> String {
+        time::OffsetDateTime::now_utc().date().to_string()
+    }
+
+    /// `journal` is in the order it was recorded, but is shown most recent first.
+    pub fn with_journal(self, mut journal: Vec<crate::journal::Entry>) -> Self {
+        journal.reverse();
+        Self { journal, ..self }
+    }
+
+    pub fn with_links(self, backlinks: Vec<crate::recipe::Recipe>, dangling: Vec<String>) -> Self {
+        Self {
+            backlinks,
+            dangling,
+            ..self
+        }
+    }
 }
 
 impl From<crate::recipe::Recipe> for Recipe<'static> {
@@ -42,8 +137,13 @@ impl From<crate::recipe::Recipe> for Recipe<'static> {
         Self {
             parent: &LAYOUT,
             search_bar: Default::default(),
+            steps: recipe.steps(),
             recipe,
             title,
+            in_stock: Default::default(),
+            backlinks: Default::default(),
+            dangling: Default::default(),
+            journal: Default::default(),
         }
     }
 }
@@ -56,6 +156,227 @@ impl Deref for Recipe<'_> {
     }
 }
 
+#[derive(Template)]
+#[template(path = "cook.html")]
+pub struct Cook<'c> {
+    parent: &'c Layout,
+    url: String,
+    steps: Vec<crate::steps::Step>,
+    title: String,
+}
+
+impl Cook<'_> {
+    pub fn new(recipe: &crate::recipe::Recipe) -> Self {
+        Self {
+            parent: &LAYOUT,
+            url: recipe.url().unwrap_or_default(),
+            steps: recipe.steps(),
+            title: String::from(recipe.title().unwrap_or("Unknown")),
+        }
+    }
+}
+
+impl Deref for Cook<'_> {
+    type Target = Layout;
+
+    fn deref(&self) -> &Self::Target {
+        self.parent
+    }
+}
+
+#[derive(Template)]
+#[template(path = "history.html")]
+pub struct History<'h> {
+    parent: &'h Layout,
+    search_bar: SearchBar,
+    commits: Option<Vec<crate::history::Commit>>,
+    snapshots: Vec<crate::snapshots::Snapshot>,
+    title: String,
+    url: String,
+}
+
+impl History<'_> {
+    /// `commits` is `None` if the recipe isn't tracked by git.
+    pub fn new(
+        recipe: &crate::recipe::Recipe,
+        commits: Option<Vec<crate::history::Commit>>,
+        snapshots: Vec<crate::snapshots::Snapshot>,
+    ) -> Self {
+        Self {
+            parent: &LAYOUT,
+            search_bar: Default::default(),
+            commits,
+            snapshots,
+            title: String::from(recipe.title().unwrap_or("Unknown")),
+            url: recipe.url().unwrap_or_default(),
+        }
+    }
+}
+
+impl Deref for History<'_> {
+    type Target = Layout;
+
+    fn deref(&self) -> &Self::Target {
+        self.parent
+    }
+}
+
+#[derive(Template)]
+#[template(path = "snapshot.html")]
+pub struct Snapshot<'s> {
+    parent: &'s Layout,
+    search_bar: SearchBar,
+    snapshot: crate::snapshots::Snapshot,
+    previous: crate::recipe::Recipe,
+    title: String,
+    url: String,
+}
+
+impl Snapshot<'_> {
+    /// `previous` is the recipe as it was when `snapshot` was taken.
+    pub fn new(
+        recipe: &crate::recipe::Recipe,
+        snapshot: crate::snapshots::Snapshot,
+        previous: crate::recipe::Recipe,
+    ) -> Self {
+        Self {
+            parent: &LAYOUT,
+            search_bar: Default::default(),
+            snapshot,
+            previous,
+            title: String::from(recipe.title().unwrap_or("Unknown")),
+            url: recipe.url().unwrap_or_default(),
+        }
+    }
+}
+
+impl Deref for Snapshot<'_> {
+    type Target = Layout;
+
+    fn deref(&self) -> &Self::Target {
+        self.parent
+    }
+}
+
+#[derive(Template)]
+#[template(path = "diff.html")]
+pub struct Diff<'d> {
+    parent: &'d Layout,
+    search_bar: SearchBar,
+    from: Option<String>,
+    to: String,
+    lines: Vec<crate::history::DiffLine>,
+    title: String,
+    url: String,
+}
+
+impl Diff<'_> {
+    pub fn new(
+        recipe: &crate::recipe::Recipe,
+        from: Option<String>,
+        to: String,
+        lines: Vec<crate::history::DiffLine>,
+    ) -> Self {
+        Self {
+            parent: &LAYOUT,
+            search_bar: Default::default(),
+            from,
+            to,
+            lines,
+            title: String::from(recipe.title().unwrap_or("Unknown")),
+            url: recipe.url().unwrap_or_default(),
+        }
+    }
+}
+
+impl Deref for Diff<'_> {
+    type Target = Layout;
+
+    fn deref(&self) -> &Self::Target {
+        self.parent
+    }
+}
+
+#[derive(Template)]
+#[template(path = "inventory.html")]
+pub struct Inventory<'i> {
+    parent: &'i Layout,
+    search_bar: SearchBar,
+    inventory: crate::inventory::Inventory,
+}
+
+impl Inventory<'_> {
+    pub fn new(inventory: crate::inventory::Inventory) -> Self {
+        Self {
+            parent: &LAYOUT,
+            search_bar: Default::default(),
+            inventory,
+        }
+    }
+}
+
+impl Deref for Inventory<'_> {
+    type Target = Layout;
+
+    fn deref(&self) -> &Self::Target {
+        self.parent
+    }
+}
+
+#[derive(Template)]
+#[template(path = "meal_plan.html")]
+pub struct MealPlan<'m> {
+    parent: &'m Layout,
+    search_bar: SearchBar,
+    plan: crate::mealplan::MealPlan,
+    weeks: Vec<String>,
+}
+
+impl MealPlan<'_> {
+    pub fn new(plan: crate::mealplan::MealPlan, weeks: Vec<String>) -> Self {
+        Self {
+            parent: &LAYOUT,
+            search_bar: Default::default(),
+            plan,
+            weeks,
+        }
+    }
+}
+
+impl Deref for MealPlan<'_> {
+    type Target = Layout;
+
+    fn deref(&self) -> &Self::Target {
+        self.parent
+    }
+}
+
+#[derive(Template)]
+#[template(path = "shopping_list.html")]
+pub struct ShoppingList<'s> {
+    parent: &'s Layout,
+    search_bar: SearchBar,
+    list: crate::shopping::ShoppingList,
+}
+
+impl ShoppingList<'_> {
+    pub fn new(list: crate::shopping::ShoppingList) -> Self {
+        Self {
+            parent: &LAYOUT,
+            search_bar: Default::default(),
+            list,
+        }
+    }
+}
+
+impl Deref for ShoppingList<'_> {
+    type Target = Layout;
+
+    fn deref(&self) -> &Self::Target {
+        self.parent
+    }
+}
+
 #[derive(Template)]
 #[template(path = "search.html")]
 pub struct Search<'s> {
@@ -73,16 +394,125 @@ impl Search<'_> {
         }
     }
 
+    pub fn with_filters(mut self, filters: crate::search::Filters) -> Self {
+        self.search_bar.filters = filters;
+        self
+    }
+
+    /// Whether the filter with the given URL parameter (e.g. `tag`) is applied for `value`.
+    pub fn has_filter(&self, param: impl AsRef<str>, value: impl AsRef<str>) -> bool {
+        crate::search::Filter::from_param(param)
+            .is_some_and(|filter| self.search_bar.filters.contains(&filter, value))
+    }
+
+    /// A link narrowing the current search by the filter with the given URL parameter.
+    pub fn add_filter_url(&self, param: impl AsRef<str>, value: impl AsRef<str>) -> String {
+        match crate::search::Filter::from_param(param) {
+            Some(filter) => self
+                .search_bar
+                .url(&self.search_bar.filters.with(filter, value.as_ref())),
+            None => self.search_bar.url(&self.search_bar.filters),
+        }
+    }
+
+    /// A link to the current search without the filter with the given URL parameter.
+    pub fn remove_filter_url(&self, param: impl AsRef<str>, value: impl AsRef<str>) -> String {
+        match crate::search::Filter::from_param(param) {
+            Some(filter) => self
+                .search_bar
+                .url(&self.search_bar.filters.without(&filter, value)),
+            None => self.search_bar.url(&self.search_bar.filters),
+        }
+    }
+
+    /// The URL parameter filtering by the custom field `name`.
+    pub fn field_param(&self, name: &str) -> String {
+        crate::search::Filter::Field(String::from(name)).param()
+    }
+
+    /// A link to a new search, filtered only by the given URL parameter.
+    pub fn filter_url(&self, param: &str, value: impl AsRef<str>) -> String {
+        let filters = crate::search::Filters::from_params([(param, value.as_ref())]);
+        SearchBar::default().url(&filters)
+    }
+
     pub fn has_many_categories(&self) -> bool {
         self.results.categories().keys().len() > 1
     }
 
-    pub fn has_many_tags(&self) -> bool {
-        self.results.tags().keys().len() > 1
+    /// The custom fields with more than one value among the results, by name.
+    pub fn field_facets(&self) -> Vec<(&String, &BTreeMap<String, usize>)> {
+        self.results
+            .fields()
+            .iter()
+            .filter(|(_, values)| values.len() > 1)
+            .collect()
+    }
+
+    pub fn has_many_languages(&self) -> bool {
+        self.results.languages().keys().len() > 1
+    }
+
+    pub fn has_many_libraries(&self) -> bool {
+        self.results.libraries().keys().len() > 1
+    }
+
+    pub fn has_many_sites(&self) -> bool {
+        self.results.sites().keys().len() > 1
+    }
+
+    pub fn has_many_sources(&self) -> bool {
+        self.results.sources().keys().len() > 1
+    }
+
+    /// The tags among the results which have more than one to choose from, grouped by namespace
+    /// (those without one coming first, under `""`). Each tag is given in full, and by its name
+    /// within the namespace, with its count.
+    pub fn tag_facets(&self) -> Vec<(&str, Vec<TagFacet<'_>>)> {
+        self.results
+            .tags()
+            .iter()
+            .filter(|(_, tags)| tags.len() > 1)
+            .map(|(namespace, tags)| {
+                let tags = tags
+                    .iter()
+                    .map(|(tag, count)| (tag.as_str(), crate::tags::split(tag).1, *count))
+                    .collect();
+                (namespace.as_str(), tags)
+            })
+            .collect()
+    }
+
+    pub fn has_ratings(&self) -> bool {
+        !self.results.ratings().is_empty() || self.results.favorites() > 0
+    }
+
+    /// For each rating among the results (best first), how many are rated at least that.
+    pub fn rating_facets(&self) -> Vec<(u8, usize)> {
+        let ratings = self.results.ratings();
+        (1..=5)
+            .rev()
+            .filter(|rating: &u8| ratings.contains_key(&rating.to_string()))
+            .map(|rating| {
+                let count = ratings
+                    .iter()
+                    .filter(|(r, _)| r.parse::<u8>().is_ok_and(|r| r >= rating))
+                    .map(|(_, count)| count)
+                    .sum();
+                (rating, count)
+            })
+            .collect()
     }
 
     pub fn is_filterable(&self) -> bool {
-        self.has_many_categories() || self.has_many_tags()
+        self.has_many_categories()
+            || !self.field_facets().is_empty()
+            || self.has_many_languages()
+            || self.has_many_libraries()
+            || self.has_many_sites()
+            || self.has_many_sources()
+            || self.has_ratings()
+            || !self.tag_facets().is_empty()
     }
 }
 